@@ -0,0 +1,54 @@
+use clap::Parser;
+use tracing_subscriber::fmt;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::EnvFilter;
+
+type BoxError = Box<dyn ::std::error::Error + Send + Sync + 'static>;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long)]
+    id: String,
+
+    #[arg(short, long)]
+    secret: String,
+
+    #[arg(short)]
+    bucket_name: String,
+
+    #[arg(short)]
+    file_name: String,
+
+    #[arg(short)]
+    output: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), BoxError> {
+    tracing_subscriber::registry()
+        .with(fmt::layer())
+        .with(EnvFilter::from_default_env())
+        .init();
+
+    let args = Args::parse();
+    let client = rustblaze::Client::new(args.id, args.secret);
+    let bucket = match client.bucket(args.bucket_name).await? {
+        Some(b) => b,
+        None => return Err("bucket does not exits".into()),
+    };
+
+    let download = bucket.download_file(&args.file_name).send().await?;
+    println!(
+        "{} ({} bytes, {})",
+        download.name(),
+        download.content_length(),
+        download.content_type()
+    );
+
+    let mut reader = Box::pin(download.into_reader());
+    let mut output = tokio::fs::File::create(args.output).await?;
+    tokio::io::copy(&mut reader, &mut output).await?;
+
+    Ok(())
+}
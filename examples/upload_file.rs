@@ -1,4 +1,3 @@
-use bytes::Buf;
 use clap::Parser;
 use tracing_subscriber::fmt;
 use tracing_subscriber::prelude::*;
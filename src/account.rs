@@ -1,5 +1,12 @@
 use std::sync::{Arc, Mutex};
 
+use serde::Deserialize;
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
+
+use crate::error::ErrorKind;
+use crate::key::Capability;
+use crate::{Error, Result};
+
 #[derive(Clone, Debug)]
 pub(crate) struct Account {
     inner: Arc<Inner>,
@@ -25,12 +32,19 @@ impl Account {
         let mut guard = self.inner.authorized.lock().unwrap();
         *guard = Some(authorized);
     }
+
+    /// Serializes authorization attempts, so that concurrent callers finding
+    /// the token missing or expired authorize only once.
+    pub async fn lock_authorization(&self) -> AsyncMutexGuard<'_, ()> {
+        self.inner.authorizing.lock().await
+    }
 }
 
 #[derive(Debug)]
 struct Inner {
     app_key: ApplicationKey,
     authorized: Mutex<Option<Authorized>>,
+    authorizing: AsyncMutex<()>,
 }
 
 impl Inner {
@@ -38,6 +52,7 @@ impl Inner {
         Self {
             app_key: ApplicationKey::new(id, secret),
             authorized: Mutex::new(None),
+            authorizing: AsyncMutex::new(()),
         }
     }
 }
@@ -58,11 +73,216 @@ impl ApplicationKey {
 pub(crate) struct Authorized {
     pub id: String,
     pub storage_api_info: StorageApiInfo,
+    pub allowed: Allowed,
+    pub application_key_expiration_timestamp: Option<i64>,
     pub token: String,
 }
 
+impl Authorized {
+    pub fn account_info(&self) -> AccountInfo {
+        AccountInfo {
+            account_id: self.id.clone(),
+            capabilities: self.allowed.capabilities.clone(),
+            bucket_id: self.allowed.bucket_id.clone(),
+            bucket_name: self.allowed.bucket_name.clone(),
+            name_prefix: self.allowed.name_prefix.clone(),
+            api_url: self.storage_api_info.url.clone(),
+            download_url: self.storage_api_info.download_url.clone(),
+            s3_api_url: self.storage_api_info.s3_api_url.clone(),
+            recommended_part_size: self.storage_api_info.recommended_part_size,
+            absolute_minimum_part_size: self.storage_api_info.absolute_minimum_part_size,
+            application_key_expiration_timestamp: self.application_key_expiration_timestamp,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct StorageApiInfo {
     pub url: String,
     pub download_url: String,
+    pub s3_api_url: String,
+    pub recommended_part_size: u64,
+    pub absolute_minimum_part_size: u64,
+}
+
+/// What the application key of a client may do, as reported when it was
+/// authorized.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Allowed {
+    pub capabilities: Vec<Capability>,
+    pub bucket_id: Option<String>,
+    pub bucket_name: Option<String>,
+    pub name_prefix: Option<String>,
+}
+
+impl Allowed {
+    /// Fails with [`ErrorKind::Unauthorized`] if the key is restricted to
+    /// another bucket than the one given by id and name, or to names that
+    /// `file_name` does not start with. Parts that are not given are not
+    /// checked.
+    pub fn check(
+        &self,
+        bucket_id: Option<&str>,
+        bucket_name: Option<&str>,
+        file_name: Option<&str>,
+    ) -> Result<()> {
+        if let Some(allowed_id) = &self.bucket_id {
+            let id_ok = bucket_id.is_none_or(|id| id == allowed_id);
+            let name_ok = bucket_name.is_none_or(|name| self.bucket_name.as_deref() == Some(name));
+            if !id_ok || !name_ok {
+                let bucket = bucket_name.or(bucket_id).unwrap_or_default();
+                return Err(Error::new(
+                    ErrorKind::Unauthorized,
+                    format!(
+                        "application key is not allowed to access bucket `{}`",
+                        bucket
+                    ),
+                ));
+            }
+        }
+
+        if let (Some(prefix), Some(file_name)) = (&self.name_prefix, file_name) {
+            if !file_name.starts_with(prefix.as_str()) {
+                return Err(Error::new(
+                    ErrorKind::Unauthorized,
+                    format!(
+                        "application key is only allowed to access names starting with `{}`, not `{}`",
+                        prefix, file_name
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The account and the application key a client is authorized with.
+#[derive(Clone, Debug)]
+pub struct AccountInfo {
+    pub account_id: String,
+    pub capabilities: Vec<Capability>,
+    /// The only bucket the key can access, `None` if it can access all.
+    pub bucket_id: Option<String>,
+    /// Name of that bucket, `None` if it no longer exists.
+    pub bucket_name: Option<String>,
+    /// The key can only access files whose name starts with this.
+    pub name_prefix: Option<String>,
+    pub api_url: String,
+    pub download_url: String,
+    pub s3_api_url: String,
+    pub recommended_part_size: u64,
+    pub absolute_minimum_part_size: u64,
+    /// Milliseconds since the epoch, `None` if the key does not expire.
+    pub application_key_expiration_timestamp: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::fails_with;
+
+    /// The `storageApi` part of a v3 `b2_authorize_account` response.
+    fn storage_api(
+        bucket_id: Option<&str>,
+        bucket_name: Option<&str>,
+        name_prefix: Option<&str>,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "absoluteMinimumPartSize": 5000000,
+            "apiUrl": "https://api001.backblazeb2.com",
+            "bucketId": bucket_id,
+            "bucketName": bucket_name,
+            "capabilities": ["listBuckets", "listFiles", "readFiles"],
+            "downloadUrl": "https://f001.backblazeb2.com",
+            "infoType": "storageApi",
+            "namePrefix": name_prefix,
+            "recommendedPartSize": 100000000,
+            "s3ApiUrl": "https://s3.us-west-001.backblazeb2.com",
+        })
+    }
+
+    fn allowed(value: serde_json::Value) -> Allowed {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn unrestricted_key_allows_everything() {
+        let allowed = allowed(storage_api(None, None, None));
+
+        assert_eq!(allowed.capabilities.len(), 3);
+        assert!(allowed
+            .check(Some("id"), Some("name"), Some("file"))
+            .is_ok());
+        assert!(allowed.check(None, None, None).is_ok());
+    }
+
+    #[test]
+    fn bucket_restriction_matches_id_and_name() {
+        let allowed = allowed(storage_api(Some("id-1"), Some("bucket-1"), None));
+
+        assert!(allowed.check(Some("id-1"), None, None).is_ok());
+        assert!(allowed.check(None, Some("bucket-1"), None).is_ok());
+        assert!(allowed.check(Some("id-1"), Some("bucket-1"), None).is_ok());
+        assert!(fails_with(
+            allowed.check(Some("id-2"), None, None),
+            ErrorKind::Unauthorized
+        ));
+        assert!(fails_with(
+            allowed.check(None, Some("bucket-2"), None),
+            ErrorKind::Unauthorized
+        ));
+
+        // Both have to point at the allowed bucket.
+        assert!(fails_with(
+            allowed.check(Some("id-2"), Some("bucket-1"), None),
+            ErrorKind::Unauthorized
+        ));
+        assert!(fails_with(
+            allowed.check(Some("id-1"), Some("bucket-2"), None),
+            ErrorKind::Unauthorized
+        ));
+
+        // Without a bucket to check there is nothing to refuse.
+        assert!(allowed.check(None, None, Some("file")).is_ok());
+    }
+
+    #[test]
+    fn deleted_bucket_has_no_name_to_match() {
+        let allowed = allowed(storage_api(Some("id-1"), None, None));
+
+        assert!(allowed.check(Some("id-1"), None, None).is_ok());
+        assert!(fails_with(
+            allowed.check(None, Some("bucket-1"), None),
+            ErrorKind::Unauthorized
+        ));
+    }
+
+    #[test]
+    fn name_prefix_restricts_file_names() {
+        let allowed = allowed(storage_api(Some("id-1"), Some("bucket-1"), Some("photos/")));
+
+        assert!(allowed
+            .check(Some("id-1"), None, Some("photos/a.jpg"))
+            .is_ok());
+        assert!(allowed.check(Some("id-1"), None, Some("photos/")).is_ok());
+        assert!(fails_with(
+            allowed.check(Some("id-1"), None, Some("photos")),
+            ErrorKind::Unauthorized
+        ));
+        assert!(fails_with(
+            allowed.check(Some("id-1"), None, Some("docs/a.txt")),
+            ErrorKind::Unauthorized
+        ));
+
+        // The bucket is checked before the name.
+        assert!(fails_with(
+            allowed.check(Some("id-2"), None, Some("photos/a.jpg")),
+            ErrorKind::Unauthorized
+        ));
+
+        // Without a name the prefix is not checked.
+        assert!(allowed.check(Some("id-1"), None, None).is_ok());
+    }
 }
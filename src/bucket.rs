@@ -1,46 +1,138 @@
+mod authorization;
+mod copy;
+mod cors;
+mod create;
+mod lifecycle;
 mod list;
+mod settings;
+mod update;
+mod upload;
 
+pub(crate) use self::authorization::*;
+pub use self::authorization::{DownloadAuthorization, DownloadAuthorizationBuilder};
+pub(crate) use self::copy::*;
+pub use self::copy::{CopyFileBuilder, MetadataDirective};
+pub(crate) use self::cors::*;
+pub use self::cors::{CorsRule, CORS_OPERATIONS};
+pub use self::create::CreateBucketBuilder;
+pub(crate) use self::create::*;
+pub use self::lifecycle::{LifecyclePreview, LifecycleRule};
 pub use self::list::ListBucketsBuilder;
 pub(crate) use self::list::*;
+pub(crate) use self::settings::*;
+pub use self::settings::{
+    DefaultRetention, EncryptionMode, FileLockConfiguration, ReplicationConfiguration,
+    ReplicationDestination, ReplicationRule, ReplicationSource, RetentionMode, RetentionPeriod,
+    RetentionPeriodUnit, ServerSideEncryption,
+};
+pub use self::update::UpdateBucketBuilder;
+pub(crate) use self::update::*;
+pub use self::upload::{UploadConfig, UploadOptions};
 
-use serde::Deserialize;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
 use tokio::io::AsyncRead;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::Instrument;
 
-use crate::file::{File, ListFileNamesBuilder};
-use crate::{Client, Result};
+use crate::error::ErrorKind;
+use crate::file::{
+    CancelLargeFileRequest, CustomerKey, DeleteFileVersionBuilder, DownloadFileBuilder,
+    DownloadTarget, Encryption, File, FileAction, FileRetention, FinishLargeFileRequest,
+    GetUploadPartUrlRequest, HideFileRequest, LargeFile, LegalHold, ListFileNamesBuilder,
+    ListFileVersionsBuilder, ListPartsBuilder, ListUnfinishedLargeFilesBuilder, Part, RemoteFile,
+    StartLargeFileRequest, UnhideFileRequest, UpdateFileLegalHoldRequest,
+    UpdateFileRetentionBuilder, UploadPartUrl,
+};
+use crate::{Client, Error, Result};
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
+
+/// Who can read the files of a bucket.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BucketType {
+    /// Anyone can download files without authorization.
+    AllPublic,
+    /// Downloads need an authorization token.
+    AllPrivate,
+    /// Created by B2 to hold snapshots.
+    Snapshot,
+    /// Shared with other accounts.
+    Shared,
+    /// Only accessible through restricted means such as B2 Reserve.
+    Restricted,
+    /// A bucket type this crate does not know about yet.
+    #[serde(untagged)]
+    Other(String),
+}
 
 #[derive(Clone, Debug)]
 pub struct Bucket {
     client: Client,
+    account_id: String,
     id: String,
     name: String,
-    upload_url: UploadUrl,
+    bucket_type: BucketType,
+    info: HashMap<String, String>,
+    revision: u64,
+    options: Vec<String>,
+    cors_rules: Vec<CorsRule>,
+    lifecycle_rules: Vec<LifecycleRule>,
+    default_server_side_encryption: Option<ServerSideEncryption>,
+    file_lock_configuration: Option<FileLockConfiguration>,
+    replication_configuration: Option<ReplicationConfiguration>,
+    upload_urls: UploadUrlPool,
+    upload_config: UploadConfig,
 }
 
-#[derive(Clone, Default, Debug)]
-struct UploadUrl {
-    inner: Arc<Mutex<Option<UploadUrlInner>>>,
+/// Upload URLs of a bucket, shared by its clones.
+///
+/// B2 allows only one upload at a time per upload URL, so every upload checks
+/// out a URL of its own and puts it back once done. At most `limit` uploads
+/// run at once, so the pool never grows beyond that many URLs.
+#[derive(Clone, Debug)]
+struct UploadUrlPool {
+    idle: Arc<Mutex<Vec<UploadUrl>>>,
+    permits: Arc<Semaphore>,
+    limit: usize,
 }
 
-impl UploadUrl {
-    fn get(&self) -> Option<UploadUrlInner> {
-        let guard = self.inner.lock().unwrap();
-        (*guard).clone()
+impl UploadUrlPool {
+    fn new(limit: usize) -> Self {
+        Self {
+            idle: Default::default(),
+            permits: Arc::new(Semaphore::new(limit)),
+            limit,
+        }
+    }
+
+    /// Waits until fewer than `limit` uploads are in flight.
+    async fn acquire(&self) -> SemaphorePermit<'_> {
+        self.permits.acquire().await.unwrap()
+    }
+
+    /// Takes an idle URL that is still valid, if there is one.
+    fn take(&self, now: i64) -> Option<UploadUrl> {
+        let mut guard = self.idle.lock().unwrap();
+        guard.retain(|url| now - url.generated_at < 86400000);
+        guard.pop()
     }
 
-    fn set(&self, inner: UploadUrlInner) -> UploadUrlInner {
-        let mut guard = self.inner.lock().unwrap();
-        *guard = Some(inner.clone());
-        inner
+    /// Returns a URL after a successful upload. URLs of failed uploads are
+    /// never put back, as B2 asks for a new one in that case.
+    fn put_back(&self, url: UploadUrl) {
+        let mut guard = self.idle.lock().unwrap();
+        guard.push(url);
     }
 }
 
 #[derive(Clone, Debug)]
-struct UploadUrlInner {
+struct UploadUrl {
     url: String,
     token: String,
     generated_at: i64,
@@ -56,15 +148,27 @@ pub(crate) struct GetUploadUrlResponse {
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct UploadFileResponse {
+    #[serde(default)]
+    pub(crate) action: FileAction,
     pub(crate) account_id: String,
     pub(crate) bucket_id: String,
     pub(crate) content_length: usize,
     pub(crate) content_sha1: Option<String>,
     pub(crate) content_md5: Option<String>,
-    pub(crate) content_type: Option<String>,
-    pub(crate) file_id: String,
+    /// `null` for folders listed with a delimiter.
+    pub(crate) file_id: Option<String>,
     pub(crate) file_name: String,
     pub(crate) upload_timestamp: i64,
+    #[serde(default)]
+    pub(crate) content_type: Option<String>,
+    #[serde(default)]
+    pub(crate) file_info: HashMap<String, String>,
+    #[serde(default)]
+    pub(crate) server_side_encryption: Option<ServerSideEncryption>,
+    #[serde(default)]
+    pub(crate) file_retention: Option<Protected<FileRetention>>,
+    #[serde(default)]
+    pub(crate) legal_hold: Option<Protected<LegalHold>>,
 }
 
 impl Bucket {
@@ -72,20 +176,124 @@ impl Bucket {
         self.name.as_str()
     }
 
-    async fn get_or_try_get_upload_url(&self) -> Result<UploadUrlInner> {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// The account that owns the bucket.
+    pub fn account_id(&self) -> &str {
+        self.account_id.as_str()
+    }
+
+    pub fn bucket_type(&self) -> &BucketType {
+        &self.bucket_type
+    }
+
+    /// Custom information stored with the bucket.
+    pub fn info(&self) -> &HashMap<String, String> {
+        &self.info
+    }
+
+    /// Incremented by B2 on every update of the bucket, see
+    /// [`UpdateBucketBuilder::if_revision_is`].
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Features enabled for the bucket, e.g. `s3`.
+    pub fn options(&self) -> &[String] {
+        &self.options
+    }
+
+    pub fn cors_rules(&self) -> &[CorsRule] {
+        &self.cors_rules
+    }
+
+    /// Whether any CORS rule of the bucket lets a browser on `origin` make
+    /// `operation` calls sending the given request `headers`.
+    pub fn cors_allows<S: AsRef<str>>(&self, origin: &str, operation: &str, headers: &[S]) -> bool {
+        self.cors_rules
+            .iter()
+            .any(|rule| rule.allows(origin, operation, headers))
+    }
+
+    pub fn lifecycle_rules(&self) -> &[LifecycleRule] {
+        &self.lifecycle_rules
+    }
+
+    /// Encryption applied to files uploaded without one, `None` if the key
+    /// is not allowed to read it.
+    pub fn default_server_side_encryption(&self) -> Option<&ServerSideEncryption> {
+        self.default_server_side_encryption.as_ref()
+    }
+
+    /// Object Lock settings, `None` if the key is not allowed to read them.
+    pub fn file_lock_configuration(&self) -> Option<&FileLockConfiguration> {
+        self.file_lock_configuration.as_ref()
+    }
+
+    /// Replication settings, `None` if the key is not allowed to read them.
+    pub fn replication_configuration(&self) -> Option<&ReplicationConfiguration> {
+        self.replication_configuration.as_ref()
+    }
+
+    /// Fetches the settings of the bucket again, e.g. after it was changed
+    /// elsewhere.
+    pub async fn refresh(&mut self) -> Result<()> {
+        let bucket = self
+            .client
+            ._list_buckets(ListBucketsRequest {
+                bucket_id: Some(self.id.clone()),
+                ..Default::default()
+            })
+            .await?
+            .buckets
+            .into_iter()
+            .find(|b| b.bucket_id == self.id)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "bucket no longer exists"))?;
+        *self = self.updated(bucket);
+
+        Ok(())
+    }
+
+    /// Fails fast if the application key may not access this bucket, or
+    /// `file_name` in it.
+    pub(crate) async fn check_allowed(&self, file_name: Option<&str>) -> Result<()> {
+        self.client
+            .check_allowed(Some(&self.id), Some(&self.name), file_name)
+            .await
+    }
+
+    pub fn upload_config(&self) -> &UploadConfig {
+        &self.upload_config
+    }
+
+    /// Replaces the upload configuration of this bucket.
+    ///
+    /// Changing [`UploadConfig::max_upload_urls`] gives this bucket a pool of
+    /// upload URLs of its own, no longer shared with its existing clones.
+    pub fn set_upload_config(&mut self, config: UploadConfig) -> &mut Self {
+        if config.upload_url_limit() != self.upload_urls.limit {
+            self.upload_urls = UploadUrlPool::new(config.upload_url_limit());
+        }
+        self.upload_config = config;
+        self
+    }
+
+    async fn get_or_try_get_upload_url(&self) -> Result<UploadUrl> {
         let now: i64 = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
             .as_millis()
             .try_into()
             .unwrap();
-        match self.upload_url.get() {
-            Some(inner) if now - inner.generated_at < 86400000 => Ok(inner),
-            _ => self.get_upload_url().await,
+        match self.upload_urls.take(now) {
+            Some(url) => Ok(url),
+            None => self.get_upload_url().await,
         }
     }
 
-    async fn get_upload_url(&self) -> Result<UploadUrlInner> {
+    async fn get_upload_url(&self) -> Result<UploadUrl> {
         let now: i64 = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
@@ -93,22 +301,50 @@ impl Bucket {
             .try_into()
             .unwrap();
         let res = self.client.get_upload_url(self.id.clone()).await?;
-        let upload_url = UploadUrlInner {
+        let upload_url = UploadUrl {
             url: res.upload_url,
             token: res.authorization_token,
             generated_at: now,
         };
-        self.upload_url.set(upload_url.clone());
 
         Ok(upload_url)
     }
 
     fn from_list_buckets_buckets(client: Client, bucket: ListBucketsBuckets) -> Self {
+        let upload_config = UploadConfig::default();
+
         Self {
             client,
+            account_id: bucket.account_id,
             id: bucket.bucket_id,
             name: bucket.bucket_name,
-            upload_url: Default::default(),
+            bucket_type: bucket.bucket_type,
+            info: bucket.bucket_info,
+            revision: bucket.revision,
+            options: bucket.options,
+            cors_rules: bucket.cors_rules,
+            lifecycle_rules: bucket.lifecycle_rules,
+            default_server_side_encryption: bucket
+                .default_server_side_encryption
+                .and_then(Protected::into_value),
+            file_lock_configuration: bucket
+                .file_lock_configuration
+                .and_then(Protected::into_value),
+            replication_configuration: bucket
+                .replication_configuration
+                .and_then(Protected::into_value),
+            upload_urls: UploadUrlPool::new(upload_config.upload_url_limit()),
+            upload_config,
+        }
+    }
+
+    /// This bucket with the settings B2 returned after changing it, sharing
+    /// its upload configuration and upload URLs.
+    fn updated(&self, bucket: ListBucketsBuckets) -> Self {
+        Self {
+            upload_urls: self.upload_urls.clone(),
+            upload_config: self.upload_config.clone(),
+            ..Self::from_list_buckets_buckets(self.client.clone(), bucket)
         }
     }
 
@@ -119,62 +355,394 @@ impl Bucket {
             .collect()
     }
 
+    pub fn update(&self) -> UpdateBucketBuilder {
+        UpdateBucketBuilder::new(self.clone())
+    }
+
+    /// Deletes the bucket, which has to be empty.
+    pub async fn delete(self) -> Result<()> {
+        self.check_allowed(None).await?;
+
+        let req = DeleteBucketRequest {
+            account_id: Default::default(),
+            bucket_id: self.id.clone(),
+        };
+
+        self.client._delete_bucket(req).await
+    }
+
     pub fn list_files(&self) -> ListFileNamesBuilder {
         ListFileNamesBuilder::new(self.client.clone(), &self.id)
     }
 
-    pub async fn upload_file<P: AsRef<Path>>(&self, path: P, name: String) -> Result<File> {
-        let _root_span = tracing::trace_span!("upload_file").entered();
-        let url_span = tracing::trace_span!("get_url").entered();
-        tracing::trace!("getting upload url");
-        let start = SystemTime::now();
-        let upload_url = self.get_or_try_get_upload_url().await?;
-        let elapsed = start.elapsed().unwrap();
-        tracing::trace!("successfully got upload url, took {:?}", elapsed);
-        url_span.exit();
-
-        let _inner_span = tracing::trace_span!("inner");
-        let res = self
-            .client
-            .upload_file(upload_url.url, upload_url.token, path, name)
-            .await?;
-        let file = File {
-            id: res.file_id,
-            name: res.file_name,
-            size: res.content_length,
-            upload_timestamp: res.upload_timestamp,
+    /// Lists all versions of the files in the bucket, including hide markers
+    /// and unfinished large files.
+    pub fn list_file_versions(&self) -> ListFileVersionsBuilder {
+        ListFileVersionsBuilder::new(self.client.clone(), &self.id)
+    }
+
+    pub fn download_file<T: AsRef<str>>(&self, name: T) -> DownloadFileBuilder {
+        DownloadFileBuilder::by_name(self.client.clone(), &self.name, name)
+    }
+
+    /// Requests a token for downloading files whose name starts with
+    /// `file_name_prefix` during `valid_duration`, at most a week.
+    pub fn download_authorization<T: AsRef<str>>(
+        &self,
+        file_name_prefix: T,
+        valid_duration: Duration,
+    ) -> DownloadAuthorizationBuilder {
+        DownloadAuthorizationBuilder::new(self.clone(), file_name_prefix, valid_duration)
+    }
+
+    /// Opens the latest version of the file for random access reads.
+    pub async fn open_file<T: AsRef<str>>(&self, name: T) -> Result<RemoteFile> {
+        let target = DownloadTarget::Name {
+            bucket_name: self.name.clone(),
+            file_name: name.as_ref().to_string(),
+        };
+
+        RemoteFile::open(self.client.clone(), target, None).await
+    }
+
+    /// Like [`Bucket::open_file`], for a file encrypted with `customer_key`
+    /// (SSE-C).
+    pub async fn open_file_with_key<T: AsRef<str>>(
+        &self,
+        name: T,
+        customer_key: CustomerKey,
+    ) -> Result<RemoteFile> {
+        let target = DownloadTarget::Name {
+            bucket_name: self.name.clone(),
+            file_name: name.as_ref().to_string(),
+        };
+
+        RemoteFile::open(self.client.clone(), target, Some(customer_key)).await
+    }
+
+    /// Copies a file, which may live in another bucket of the account, to
+    /// `name` in this bucket.
+    pub fn copy_file<T, U>(&self, source_file_id: T, name: U) -> CopyFileBuilder
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+    {
+        CopyFileBuilder::new(self.clone(), source_file_id, name)
+    }
+
+    /// Deletes one version of a file. Deleting the latest version makes the
+    /// previous one, if any, the current version of the file.
+    pub fn delete_file_version<T, U>(&self, name: T, id: U) -> DeleteFileVersionBuilder
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+    {
+        DeleteFileVersionBuilder::new(self.client.clone(), name, id)
+    }
+
+    /// Sets the Object Lock retention of one version of a file, see
+    /// [`UpdateFileRetentionBuilder`].
+    pub fn update_file_retention<T, U>(
+        &self,
+        name: T,
+        id: U,
+        file_retention: FileRetention,
+    ) -> UpdateFileRetentionBuilder
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+    {
+        UpdateFileRetentionBuilder::new(self.client.clone(), name, id, file_retention)
+    }
+
+    /// Turns the Object Lock legal hold of one version of a file on or off.
+    pub async fn update_file_legal_hold<T, U>(
+        &self,
+        name: T,
+        id: U,
+        legal_hold: LegalHold,
+    ) -> Result<()>
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+    {
+        self.check_allowed(Some(name.as_ref())).await?;
+
+        let req = UpdateFileLegalHoldRequest {
+            file_name: name.as_ref().to_string(),
+            file_id: id.as_ref().to_string(),
+            legal_hold,
+        };
+        self.client._update_file_legal_hold(req).await?;
+
+        Ok(())
+    }
+
+    /// Hides a file, so that it is no longer listed or downloadable by name,
+    /// by uploading a hide marker. Returns the hide marker.
+    ///
+    /// Earlier versions are kept and can still be downloaded by id.
+    pub async fn hide_file<T: AsRef<str>>(&self, name: T) -> Result<File> {
+        self.check_allowed(Some(name.as_ref())).await?;
+
+        let req = HideFileRequest {
+            bucket_id: self.id.clone(),
+            file_name: name.as_ref().to_string(),
         };
+        let res = self.client._hide_file(req).await?;
 
-        Ok(file)
+        Ok(res.into())
+    }
+
+    /// Deletes the hide marker of a hidden file, returning the version that
+    /// is visible again.
+    pub async fn unhide_file<T: AsRef<str>>(&self, name: T) -> Result<File> {
+        self.check_allowed(Some(name.as_ref())).await?;
+
+        let req = UnhideFileRequest {
+            bucket_id: self.id.clone(),
+            file_name: name.as_ref().to_string(),
+        };
+        let res = self.client._unhide_file(req).await?;
+
+        Ok(res.into())
+    }
+
+    /// Uploads the file at `path`, switching to a parallel large file upload
+    /// when it is bigger than one part.
+    ///
+    /// Large file uploads of a path are resumable: if an earlier upload of the
+    /// same file was interrupted, the parts it already uploaded are verified
+    /// by SHA1 and only the missing ones are sent.
+    pub async fn upload_file<P: AsRef<Path>>(&self, path: P, name: String) -> Result<File> {
+        self.upload_file_with_options(path, name, &UploadOptions::default())
+            .await
     }
 
+    /// Like [`Bucket::upload_file`], with the content type and file info in
+    /// `options`. Unless given there, the modification time of the file is
+    /// stored as `src_last_modified_millis`.
+    pub async fn upload_file_with_options<P: AsRef<Path>>(
+        &self,
+        path: P,
+        name: String,
+        options: &UploadOptions,
+    ) -> Result<File> {
+        let span = tracing::trace_span!("upload_file");
+        async {
+            let file = tokio::fs::File::open(path).await?;
+            let metadata = file.metadata().await?;
+
+            // Also identifies the local file when looking for an interrupted
+            // upload of it to resume.
+            let mut options = options.clone();
+            if !options.info().contains_key("src_last_modified_millis") {
+                if let Ok(modified) = metadata.modified() {
+                    options.src_last_modified(modified);
+                }
+            }
+
+            self.upload(file, name, Some(metadata.len()), &options, true)
+                .await
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Uploads everything `reader` yields, switching to a parallel large file
+    /// upload once it is bigger than one part.
     pub async fn upload_file_from_reader<R, S>(&self, reader: R, name: S) -> Result<File>
     where
         R: AsyncRead + Unpin,
         S: AsRef<str>,
     {
-        let _root_span = tracing::trace_span!("upload_file").entered();
-        let url_span = tracing::trace_span!("get_url").entered();
-        tracing::trace!("getting upload url");
-        let start = SystemTime::now();
-        let upload_url = self.get_or_try_get_upload_url().await?;
-        let elapsed = start.elapsed().unwrap();
-        tracing::trace!("successfully got upload url, took {:?}", elapsed);
-        url_span.exit();
-
-        let inner_span = tracing::trace_span!("inner").entered();
+        self.upload_file_from_reader_with_options(reader, name, &UploadOptions::default())
+            .await
+    }
+
+    /// Like [`Bucket::upload_file_from_reader`], with the content type and
+    /// file info in `options`.
+    pub async fn upload_file_from_reader_with_options<R, S>(
+        &self,
+        reader: R,
+        name: S,
+        options: &UploadOptions,
+    ) -> Result<File>
+    where
+        R: AsyncRead + Unpin,
+        S: AsRef<str>,
+    {
+        let span = tracing::trace_span!("upload_file");
+        self.upload(reader, name.as_ref().to_owned(), None, options, false)
+            .instrument(span)
+            .await
+    }
+
+    /// Uploads `data` with a single request, using an upload URL checked out
+    /// of the pool and moving on to a new one whenever an attempt fails in a
+    /// way B2 says calls for a new URL.
+    async fn upload_single(
+        &self,
+        name: String,
+        data: Bytes,
+        options: &UploadOptions,
+    ) -> Result<File> {
+        let res = self
+            .client
+            .retry_policy()
+            .retry_when(
+                || async {
+                    let _permit = self.upload_urls.acquire().await;
+
+                    let url_span = tracing::trace_span!("get_url");
+                    tracing::trace!("getting upload url");
+                    let start = SystemTime::now();
+                    let upload_url = self
+                        .get_or_try_get_upload_url()
+                        .instrument(url_span)
+                        .await?;
+                    let elapsed = start.elapsed().unwrap();
+                    tracing::trace!("successfully got upload url, took {:?}", elapsed);
+
+                    let inner_span = tracing::trace_span!("inner");
+                    let res = self
+                        .client
+                        .upload_file(
+                            upload_url.url.clone(),
+                            upload_url.token.clone(),
+                            name.clone(),
+                            data.clone(),
+                            &self.upload_config,
+                            options,
+                        )
+                        .instrument(inner_span)
+                        .await;
+                    if res.is_ok() {
+                        self.upload_urls.put_back(upload_url);
+                    }
+
+                    res
+                },
+                Error::is_retryable_upload,
+            )
+            .await?;
+
+        Ok(res.into())
+    }
+
+    /// Starts a large file, whose contents are then uploaded in parts with
+    /// [`Bucket::upload_part`] and assembled with [`Bucket::finish_large_file`].
+    pub async fn start_large_file<T: AsRef<str>>(&self, name: T) -> Result<LargeFile> {
+        self.start_large_file_with_info(
+            name.as_ref(),
+            "b2/x-auto",
+            HashMap::new(),
+            self.upload_config.upload_encryption(),
+            None,
+            None,
+        )
+        .await
+    }
+
+    async fn start_large_file_with_info(
+        &self,
+        name: &str,
+        content_type: &str,
+        file_info: HashMap<String, String>,
+        encryption: Option<&Encryption>,
+        file_retention: Option<&FileRetention>,
+        legal_hold: Option<LegalHold>,
+    ) -> Result<LargeFile> {
+        self.check_allowed(Some(name)).await?;
+
+        let req = StartLargeFileRequest {
+            bucket_id: self.id.clone(),
+            file_name: name.to_string(),
+            content_type: content_type.to_string(),
+            file_info,
+            server_side_encryption: encryption.map(Encryption::to_request),
+            file_retention: file_retention.cloned(),
+            legal_hold,
+        };
+        let res = self.client._start_large_file(req).await?;
+
+        Ok(LargeFile::from_response(res))
+    }
+
+    pub fn list_unfinished_large_files(&self) -> ListUnfinishedLargeFilesBuilder {
+        ListUnfinishedLargeFilesBuilder::new(self.client.clone(), &self.id)
+    }
+
+    pub fn list_parts(&self, file: &LargeFile) -> ListPartsBuilder {
+        ListPartsBuilder::new(self.client.clone(), file.id())
+    }
+
+    pub async fn get_upload_part_url(&self, file: &LargeFile) -> Result<UploadPartUrl> {
+        self.client
+            .retry_policy()
+            .retry(|| self.get_upload_part_url_once(file))
+            .await
+    }
+
+    /// Gets an upload URL for a part without retrying, for part uploads
+    /// that retry it along with the part.
+    async fn get_upload_part_url_once(&self, file: &LargeFile) -> Result<UploadPartUrl> {
+        let req = GetUploadPartUrlRequest {
+            file_id: file.id().to_string(),
+        };
+        let res = self.client._get_upload_part_url(req).await?;
+
+        Ok(UploadPartUrl {
+            url: res.upload_url,
+            token: res.authorization_token,
+        })
+    }
+
+    /// Uploads part `part_number` of a large file, numbering starts at 1.
+    ///
+    /// Every part except the last one must be at least as big as the
+    /// account's minimum part size.
+    pub async fn upload_part<B: Into<Bytes>>(
+        &self,
+        file: &LargeFile,
+        url: &UploadPartUrl,
+        part_number: u16,
+        data: B,
+    ) -> Result<Part> {
         let res = self
             .client
-            .upload_file_from_reader(
-                upload_url.url,
-                upload_url.token,
-                reader,
-                name.as_ref().to_owned(),
+            ._upload_part(
+                url.url.clone(),
+                url.token.clone(),
+                part_number,
+                data.into(),
+                self.upload_config
+                    .upload_encryption()
+                    .and_then(Encryption::customer_key),
             )
             .await?;
-        inner_span.exit();
+        let part = Part::from(res);
+        file.add_part(part.clone());
+
+        Ok(part)
+    }
 
+    pub async fn finish_large_file(&self, file: LargeFile) -> Result<File> {
+        let req = FinishLargeFileRequest {
+            file_id: file.id().to_string(),
+            part_sha1_array: file.part_sha1_array(),
+        };
+        let res = self.client._finish_large_file(req).await?;
 
         Ok(res.into())
     }
+
+    /// Cancels a large file and deletes the parts uploaded so far.
+    pub async fn cancel_large_file(&self, file: LargeFile) -> Result<()> {
+        let req = CancelLargeFileRequest {
+            file_id: file.id().to_string(),
+        };
+
+        self.client._cancel_large_file(req).await
+    }
 }
@@ -0,0 +1,265 @@
+use std::time::Duration;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use super::Bucket;
+use crate::error::ErrorKind;
+use crate::file::file_url;
+use crate::{Error, Result};
+
+const MAX_VALID_DURATION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Characters left as-is in query values, spaces become `%20` rather than
+/// the `+` of form encoding.
+const QUERY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GetDownloadAuthorizationRequest {
+    bucket_id: String,
+    file_name_prefix: String,
+    valid_duration_in_seconds: u64,
+    #[serde(flatten)]
+    overrides: ResponseOverrides,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GetDownloadAuthorizationResponse {
+    authorization_token: String,
+}
+
+/// Response headers B2 sends instead of those stored with the file. A token
+/// with overrides only works when the same ones are given when downloading.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResponseOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    b2_content_disposition: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    b2_content_language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    b2_expires: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    b2_cache_control: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    b2_content_encoding: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    b2_content_type: Option<String>,
+}
+
+impl ResponseOverrides {
+    fn query_pairs(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("b2ContentDisposition", &self.b2_content_disposition),
+            ("b2ContentLanguage", &self.b2_content_language),
+            ("b2Expires", &self.b2_expires),
+            ("b2CacheControl", &self.b2_cache_control),
+            ("b2ContentEncoding", &self.b2_content_encoding),
+            ("b2ContentType", &self.b2_content_type),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value.as_deref()?)))
+    }
+}
+
+/// Requests a token that allows downloading files whose name starts with a
+/// prefix from a private bucket, without the application key.
+#[derive(Clone, Debug)]
+pub struct DownloadAuthorizationBuilder {
+    bucket: Bucket,
+    file_name_prefix: String,
+    valid_duration: Duration,
+    overrides: ResponseOverrides,
+}
+
+impl DownloadAuthorizationBuilder {
+    pub(crate) fn new<T: AsRef<str>>(
+        bucket: Bucket,
+        file_name_prefix: T,
+        valid_duration: Duration,
+    ) -> Self {
+        Self {
+            bucket,
+            file_name_prefix: file_name_prefix.as_ref().to_string(),
+            valid_duration,
+            overrides: Default::default(),
+        }
+    }
+
+    /// `Content-Disposition` of the response, e.g. to make browsers save the
+    /// file under a given name.
+    pub fn content_disposition<T: AsRef<str>>(&mut self, content_disposition: T) -> &mut Self {
+        self.overrides.b2_content_disposition = Some(content_disposition.as_ref().to_string());
+        self
+    }
+
+    pub fn content_language<T: AsRef<str>>(&mut self, content_language: T) -> &mut Self {
+        self.overrides.b2_content_language = Some(content_language.as_ref().to_string());
+        self
+    }
+
+    pub fn expires<T: AsRef<str>>(&mut self, expires: T) -> &mut Self {
+        self.overrides.b2_expires = Some(expires.as_ref().to_string());
+        self
+    }
+
+    pub fn cache_control<T: AsRef<str>>(&mut self, cache_control: T) -> &mut Self {
+        self.overrides.b2_cache_control = Some(cache_control.as_ref().to_string());
+        self
+    }
+
+    pub fn content_encoding<T: AsRef<str>>(&mut self, content_encoding: T) -> &mut Self {
+        self.overrides.b2_content_encoding = Some(content_encoding.as_ref().to_string());
+        self
+    }
+
+    pub fn content_type<T: AsRef<str>>(&mut self, content_type: T) -> &mut Self {
+        self.overrides.b2_content_type = Some(content_type.as_ref().to_string());
+        self
+    }
+
+    pub async fn send(&mut self) -> Result<DownloadAuthorization> {
+        if self.valid_duration.as_secs() == 0 || self.valid_duration > MAX_VALID_DURATION {
+            return Err(Error::new(
+                ErrorKind::BadRequest,
+                "download authorizations must be valid for at least a second and at most a week",
+            ));
+        }
+        self.bucket
+            .check_allowed(Some(&self.file_name_prefix))
+            .await?;
+
+        let req = GetDownloadAuthorizationRequest {
+            bucket_id: self.bucket.id.clone(),
+            file_name_prefix: self.file_name_prefix.clone(),
+            valid_duration_in_seconds: self.valid_duration.as_secs(),
+            overrides: self.overrides.clone(),
+        };
+        let res = self.bucket.client._get_download_authorization(req).await?;
+        let info = self.bucket.client.storage_api_info().await?;
+
+        Ok(DownloadAuthorization {
+            download_url: info.download_url,
+            bucket_name: self.bucket.name.clone(),
+            file_name_prefix: self.file_name_prefix.clone(),
+            token: res.authorization_token,
+            overrides: self.overrides.clone(),
+        })
+    }
+}
+
+/// A token for downloading files under a prefix of a bucket, which can be
+/// handed out as URLs that work without any other credentials.
+#[derive(Clone, Debug)]
+pub struct DownloadAuthorization {
+    download_url: String,
+    bucket_name: String,
+    file_name_prefix: String,
+    token: String,
+    overrides: ResponseOverrides,
+}
+
+impl DownloadAuthorization {
+    pub fn file_name_prefix(&self) -> &str {
+        self.file_name_prefix.as_str()
+    }
+
+    /// The token, to be sent as the `Authorization` header or query
+    /// parameter of downloads by name.
+    pub fn token(&self) -> &str {
+        self.token.as_str()
+    }
+
+    /// URL that downloads `file_name` with this authorization, including the
+    /// response overrides it was requested with.
+    pub fn url<T: AsRef<str>>(&self, file_name: T) -> Result<Url> {
+        let file_name = file_name.as_ref();
+        if !file_name.starts_with(&self.file_name_prefix) {
+            return Err(Error::new(
+                ErrorKind::BadRequest,
+                format!(
+                    "`{}` is outside of the authorized prefix `{}`",
+                    file_name, self.file_name_prefix
+                ),
+            ));
+        }
+
+        let mut url = file_url(&self.download_url, &self.bucket_name, file_name);
+        let pairs = [("Authorization", self.token.as_str())]
+            .into_iter()
+            .chain(self.overrides.query_pairs());
+        for (i, (key, value)) in pairs.enumerate() {
+            url.push(if i == 0 { '?' } else { '&' });
+            url.push_str(key);
+            url.push('=');
+            url.extend(utf8_percent_encode(value, QUERY_ENCODE_SET));
+        }
+
+        Url::parse(&url).map_err(|err| Error::new(ErrorKind::BadRequest, err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::fails_with;
+
+    fn authorization(overrides: ResponseOverrides) -> DownloadAuthorization {
+        DownloadAuthorization {
+            download_url: "https://f000.backblazeb2.com".into(),
+            bucket_name: "photos".into(),
+            file_name_prefix: "public/".into(),
+            token: "3_token/with+chars=".into(),
+            overrides,
+        }
+    }
+
+    #[test]
+    fn url_encodes_name_and_token() {
+        let url = authorization(Default::default())
+            .url("public/a b+c ü.jpg")
+            .unwrap();
+
+        assert_eq!(
+            url.as_str(),
+            "https://f000.backblazeb2.com/file/photos/public/a%20b%2Bc%20%C3%BC.jpg\
+             ?Authorization=3_token%2Fwith%2Bchars%3D"
+        );
+    }
+
+    #[test]
+    fn url_includes_overrides() {
+        let overrides = ResponseOverrides {
+            b2_content_disposition: Some("attachment; filename=\"a b.jpg\"".into()),
+            b2_content_type: Some("image/jpeg".into()),
+            ..Default::default()
+        };
+        let url = authorization(overrides).url("public/a.jpg").unwrap();
+
+        assert_eq!(
+            url.as_str(),
+            "https://f000.backblazeb2.com/file/photos/public/a.jpg\
+             ?Authorization=3_token%2Fwith%2Bchars%3D\
+             &b2ContentDisposition=attachment%3B%20filename%3D%22a%20b.jpg%22\
+             &b2ContentType=image%2Fjpeg"
+        );
+        let pairs: Vec<_> = url.query_pairs().collect();
+        assert_eq!(pairs[1].1, "attachment; filename=\"a b.jpg\"");
+    }
+
+    #[test]
+    fn url_rejects_names_outside_prefix() {
+        let auth = authorization(Default::default());
+
+        assert!(fails_with(auth.url("private/a.jpg"), ErrorKind::BadRequest));
+        assert!(auth.url("public").is_err());
+        assert!(auth.url("public/").is_ok());
+    }
+}
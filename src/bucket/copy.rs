@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+use std::ops::{Bound, Range, RangeBounds};
+
+use futures_util::{stream, StreamExt, TryStreamExt};
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+
+use super::upload::{B2_FILE_INFO_KEYS, MAX_PARTS};
+use super::Bucket;
+use crate::error::ErrorKind;
+use crate::file::{
+    ByteRange, CustomerKey, Download, DownloadFileRequest, DownloadTarget, Encryption,
+    EncryptionRequest, File, FileRetention, LargeFile, LegalHold, Part,
+};
+use crate::{Error, Result};
+
+/// Largest file B2 copies with a single `b2_copy_file` call, bigger ones are
+/// assembled from parts with `b2_copy_part`.
+const MAX_COPY_FILE_SIZE: u64 = 5_000_000_000;
+
+/// Whether a copy keeps the content type and file info of its source.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MetadataDirective {
+    #[default]
+    Copy,
+    /// Use the content type and file info given to the copy instead.
+    Replace,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CopyFileRequest {
+    source_file_id: String,
+    destination_bucket_id: String,
+    file_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range: Option<String>,
+    metadata_directive: MetadataDirective,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_info: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_server_side_encryption: Option<EncryptionRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    destination_server_side_encryption: Option<EncryptionRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_retention: Option<FileRetention>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    legal_hold: Option<LegalHold>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CopyPartRequest {
+    source_file_id: String,
+    large_file_id: String,
+    part_number: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_server_side_encryption: Option<EncryptionRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    destination_server_side_encryption: Option<EncryptionRequest>,
+}
+
+/// Copies a file into a bucket without downloading it.
+///
+/// Sources too big for a single copy are copied part by part into a large
+/// file, with as many parts in flight as the destination bucket's
+/// [`UploadConfig`](super::UploadConfig) allows for uploads. Copies are
+/// encrypted as set there unless [`CopyFileBuilder::encryption`] is given.
+#[derive(Clone, Debug)]
+pub struct CopyFileBuilder {
+    bucket: Bucket,
+    source_file_id: String,
+    file_name: String,
+    source_size: Option<u64>,
+    range: Option<(Bound<u64>, Bound<u64>)>,
+    metadata_directive: MetadataDirective,
+    content_type: Option<String>,
+    file_info: Option<HashMap<String, String>>,
+    source_customer_key: Option<CustomerKey>,
+    encryption: Option<Encryption>,
+    file_retention: Option<FileRetention>,
+    legal_hold: Option<LegalHold>,
+}
+
+impl CopyFileBuilder {
+    pub(crate) fn new<T, U>(bucket: Bucket, source_file_id: T, file_name: U) -> Self
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+    {
+        Self {
+            bucket,
+            source_file_id: source_file_id.as_ref().to_string(),
+            file_name: file_name.as_ref().to_string(),
+            source_size: Default::default(),
+            range: Default::default(),
+            metadata_directive: Default::default(),
+            content_type: Default::default(),
+            file_info: Default::default(),
+            source_customer_key: Default::default(),
+            encryption: Default::default(),
+            file_retention: Default::default(),
+            legal_hold: Default::default(),
+        }
+    }
+
+    /// Size of the source, saves looking it up before copying.
+    pub(crate) fn source_size(&mut self, source_size: u64) -> &mut Self {
+        self.source_size = Some(source_size);
+        self
+    }
+
+    /// Only copy the given byte range of the source, which must not be
+    /// empty.
+    pub fn range<R: RangeBounds<u64>>(&mut self, range: R) -> &mut Self {
+        self.range = Some((range.start_bound().cloned(), range.end_bound().cloned()));
+        self
+    }
+
+    pub fn metadata_directive(&mut self, metadata_directive: MetadataDirective) -> &mut Self {
+        self.metadata_directive = metadata_directive;
+        self
+    }
+
+    /// Content type of the copy, requires [`MetadataDirective::Replace`].
+    pub fn content_type<T: AsRef<str>>(&mut self, content_type: T) -> &mut Self {
+        self.content_type = Some(content_type.as_ref().to_string());
+        self
+    }
+
+    /// File info of the copy, requires [`MetadataDirective::Replace`].
+    pub fn file_info(&mut self, file_info: HashMap<String, String>) -> &mut Self {
+        self.file_info = Some(file_info);
+        self
+    }
+
+    /// Key the source was encrypted with, required to copy SSE-C files.
+    pub fn source_customer_key(&mut self, source_customer_key: CustomerKey) -> &mut Self {
+        self.source_customer_key = Some(source_customer_key);
+        self
+    }
+
+    /// Server-side encryption of the copy.
+    pub fn encryption(&mut self, encryption: Encryption) -> &mut Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    /// Object Lock retention of the copy, instead of the default retention
+    /// of the bucket. Requires a bucket with Object Lock enabled.
+    pub fn file_retention(&mut self, file_retention: FileRetention) -> &mut Self {
+        self.file_retention = Some(file_retention);
+        self
+    }
+
+    /// Object Lock legal hold of the copy. Requires a bucket with Object Lock
+    /// enabled.
+    pub fn legal_hold(&mut self, legal_hold: LegalHold) -> &mut Self {
+        self.legal_hold = Some(legal_hold);
+        self
+    }
+
+    pub async fn send(&mut self) -> Result<File> {
+        let byte_range = self.range.map(ByteRange::from_bounds).transpose()?;
+        self.bucket.check_allowed(Some(&self.file_name)).await?;
+
+        let encryption = self
+            .encryption
+            .as_ref()
+            .or_else(|| self.bucket.upload_config.upload_encryption());
+
+        let mut source = None;
+        let size = match self.source_size {
+            Some(size) => size,
+            None => {
+                let download = self.head_source().await?;
+                let size = download.file_size();
+                source = Some(download);
+                size
+            }
+        };
+
+        let range = match byte_range {
+            Some(range) => {
+                range.start
+                    ..range
+                        .end
+                        .map_or(size, |end| end.saturating_add(1).min(size))
+            }
+            None => 0..size,
+        };
+        if range.end.saturating_sub(range.start) <= MAX_COPY_FILE_SIZE {
+            let req = CopyFileRequest {
+                source_file_id: self.source_file_id.clone(),
+                destination_bucket_id: self.bucket.id.clone(),
+                file_name: self.file_name.clone(),
+                range: byte_range.map(|r| r.header_value()),
+                metadata_directive: self.metadata_directive,
+                content_type: self.content_type.clone(),
+                file_info: self.file_info.clone(),
+                source_server_side_encryption: self
+                    .source_customer_key
+                    .as_ref()
+                    .map(CustomerKey::to_request),
+                destination_server_side_encryption: encryption.map(Encryption::to_request),
+                file_retention: self.file_retention.clone(),
+                legal_hold: self.legal_hold,
+            };
+            let res = self.bucket.client._copy_file(req).await?;
+
+            return Ok(res.into());
+        }
+
+        // Parts carry no metadata, so the large file has to be started with
+        // that of the source when it is kept.
+        let (content_type, file_info) = match self.metadata_directive {
+            MetadataDirective::Copy => {
+                let source = match source {
+                    Some(source) => source,
+                    None => self.head_source().await?,
+                };
+                let file_info = source_file_info(source.info(), source.headers());
+                (source.content_type().to_string(), file_info)
+            }
+            MetadataDirective::Replace => (
+                self.content_type
+                    .clone()
+                    .unwrap_or_else(|| "b2/x-auto".to_string()),
+                self.file_info.clone().unwrap_or_default(),
+            ),
+        };
+
+        self.copy_large(range, &content_type, file_info, encryption)
+            .await
+    }
+
+    /// Copies `range` of the source into a new large file, cancelling it if
+    /// any part fails.
+    async fn copy_large(
+        &self,
+        range: Range<u64>,
+        content_type: &str,
+        file_info: HashMap<String, String>,
+        encryption: Option<&Encryption>,
+    ) -> Result<File> {
+        let bucket = &self.bucket;
+        let info = bucket.client.storage_api_info().await?;
+        let plan = bucket
+            .upload_config
+            .copy_plan(&info, range.end - range.start);
+        let file = bucket
+            .start_large_file_with_info(
+                &self.file_name,
+                content_type,
+                file_info,
+                encryption,
+                self.file_retention.as_ref(),
+                self.legal_hold,
+            )
+            .await?;
+        tracing::trace!(
+            file_id = file.id(),
+            part_size = plan.part_size,
+            concurrency = plan.concurrency,
+            "copying large file"
+        );
+
+        let count = (range.end - range.start).div_ceil(plan.part_size);
+        let file_ref = &file;
+        let res = stream::iter(0..count)
+            .map(|i| async move {
+                let part_number = u16::try_from(i + 1)
+                    .ok()
+                    .filter(|&n| u64::from(n) <= MAX_PARTS)
+                    .ok_or_else(|| Error::new(ErrorKind::BadRequest, "too many parts"))?;
+                let start = range.start + i * plan.part_size;
+                let end = (start + plan.part_size).min(range.end);
+
+                bucket
+                    .copy_part_encrypted(
+                        file_ref,
+                        &self.source_file_id,
+                        part_number,
+                        ByteRange::from_bounds(start..end)?,
+                        self.source_customer_key.as_ref(),
+                        encryption,
+                    )
+                    .await
+            })
+            .buffer_unordered(plan.concurrency)
+            .try_collect::<Vec<_>>()
+            .await;
+
+        match res {
+            Ok(_) => bucket.finish_large_file(file).await,
+            Err(err) => {
+                if let Err(cancel_err) = bucket.cancel_large_file(file).await {
+                    tracing::warn!("could not cancel large file: {}", cancel_err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    async fn head_source(&self) -> Result<Download> {
+        let req = DownloadFileRequest {
+            target: DownloadTarget::Id(self.source_file_id.clone()),
+            range: None,
+            head: true,
+            customer_key: self.source_customer_key.clone(),
+        };
+        let res = self.bucket.client._download_file(req).await?;
+
+        Ok(Download::from_response(res))
+    }
+}
+
+/// File info of a downloaded source. B2 sends the `b2-*` entries as the
+/// response headers they stand for, e.g. `b2-cache-control` as
+/// `Cache-Control`, so they are mapped back to their keys.
+fn source_file_info(
+    info: &HashMap<String, String>,
+    headers: &HeaderMap,
+) -> HashMap<String, String> {
+    let mut info = info.clone();
+    for key in B2_FILE_INFO_KEYS {
+        let header = key.trim_start_matches("b2-");
+        if let Some(value) = headers.get(header).and_then(|v| v.to_str().ok()) {
+            info.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    info
+}
+
+impl Bucket {
+    /// Copies a byte range of another file into part `part_number` of a
+    /// large file, numbering starts at 1.
+    ///
+    /// An unbounded range copies the whole source file. The part is
+    /// encrypted as set in the [`UploadConfig`](super::UploadConfig) of the
+    /// bucket, which has to match how the large file was started.
+    pub async fn copy_part<T, R>(
+        &self,
+        file: &LargeFile,
+        source_file_id: T,
+        part_number: u16,
+        range: R,
+    ) -> Result<Part>
+    where
+        T: AsRef<str>,
+        R: RangeBounds<u64>,
+    {
+        self.copy_part_encrypted(
+            file,
+            source_file_id.as_ref(),
+            part_number,
+            ByteRange::from_bounds(range)?,
+            None,
+            self.upload_config.upload_encryption(),
+        )
+        .await
+    }
+
+    async fn copy_part_encrypted(
+        &self,
+        file: &LargeFile,
+        source_file_id: &str,
+        part_number: u16,
+        range: ByteRange,
+        source_customer_key: Option<&CustomerKey>,
+        encryption: Option<&Encryption>,
+    ) -> Result<Part> {
+        let req = CopyPartRequest {
+            source_file_id: source_file_id.to_string(),
+            large_file_id: file.id().to_string(),
+            part_number,
+            range: (range.start != 0 || range.end.is_some()).then(|| range.header_value()),
+            source_server_side_encryption: source_customer_key.map(CustomerKey::to_request),
+            destination_server_side_encryption: encryption.map(Encryption::to_request),
+        };
+        let res = self.client._copy_part(req).await?;
+        let part = Part::from(res);
+        file.add_part(part.clone());
+
+        Ok(part)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_file_info_includes_b2_headers() {
+        let info = HashMap::from([("src_last_modified_millis".to_string(), "1".to_string())]);
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "text/plain".parse().unwrap());
+        headers.insert(
+            "content-disposition",
+            "attachment; filename=\"a b.txt\"".parse().unwrap(),
+        );
+        headers.insert("content-language", "de".parse().unwrap());
+        headers.insert("expires", "Thu, 01 Dec 2044 16:00:00 GMT".parse().unwrap());
+        headers.insert("cache-control", "max-age=3600".parse().unwrap());
+        headers.insert("content-encoding", "gzip".parse().unwrap());
+        headers.insert("x-bz-info-src_last_modified_millis", "1".parse().unwrap());
+
+        let info = source_file_info(&info, &headers);
+
+        assert_eq!(
+            info,
+            HashMap::from([
+                ("src_last_modified_millis".to_string(), "1".to_string()),
+                (
+                    "b2-content-disposition".to_string(),
+                    "attachment; filename=\"a b.txt\"".to_string()
+                ),
+                ("b2-content-language".to_string(), "de".to_string()),
+                (
+                    "b2-expires".to_string(),
+                    "Thu, 01 Dec 2044 16:00:00 GMT".to_string()
+                ),
+                ("b2-cache-control".to_string(), "max-age=3600".to_string()),
+                ("b2-content-encoding".to_string(), "gzip".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn source_file_info_without_b2_headers() {
+        let info = HashMap::from([("author".to_string(), "me".to_string())]);
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "text/plain".parse().unwrap());
+
+        assert_eq!(source_file_info(&info, &headers), info);
+    }
+}
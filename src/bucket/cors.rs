@@ -0,0 +1,410 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ErrorKind;
+use crate::{Error, Result};
+
+/// Operations a CORS rule can allow.
+pub const CORS_OPERATIONS: &[&str] = &[
+    "b2_download_file_by_name",
+    "b2_download_file_by_id",
+    "b2_upload_file",
+    "b2_upload_part",
+    "s3_delete",
+    "s3_get",
+    "s3_head",
+    "s3_post",
+    "s3_put",
+];
+
+const MAX_CORS_RULES: usize = 100;
+const MAX_AGE_SECONDS: u32 = 86400;
+
+/// Allows browsers on other origins to make the listed calls to a bucket.
+///
+/// Origins and allowed headers may contain one `*` wildcard, a lone `*`
+/// matches everything. Other origins include their scheme, as in
+/// `https://*.example.com`, or are just `https` or `http`, which matches
+/// every origin using that scheme.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorsRule {
+    #[serde(rename = "corsRuleName")]
+    pub name: String,
+    pub allowed_origins: Vec<String>,
+    pub allowed_operations: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_headers: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expose_headers: Option<Vec<String>>,
+    pub max_age_seconds: u32,
+}
+
+impl CorsRule {
+    pub fn new<T: AsRef<str>>(name: T) -> Self {
+        Self {
+            name: name.as_ref().to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn allowed_origin<T: AsRef<str>>(&mut self, origin: T) -> &mut Self {
+        self.allowed_origins.push(origin.as_ref().to_string());
+        self
+    }
+
+    /// Allows one of the [`CORS_OPERATIONS`].
+    pub fn allowed_operation<T: AsRef<str>>(&mut self, operation: T) -> &mut Self {
+        self.allowed_operations.push(operation.as_ref().to_string());
+        self
+    }
+
+    /// Allows a header in requests, checked against the headers a
+    /// preflight request announces.
+    pub fn allowed_header<T: AsRef<str>>(&mut self, header: T) -> &mut Self {
+        self.allowed_headers
+            .get_or_insert_with(Vec::new)
+            .push(header.as_ref().to_string());
+        self
+    }
+
+    /// Makes a response header readable by the browser.
+    pub fn expose_header<T: AsRef<str>>(&mut self, header: T) -> &mut Self {
+        self.expose_headers
+            .get_or_insert_with(Vec::new)
+            .push(header.as_ref().to_string());
+        self
+    }
+
+    /// How long browsers may cache the result of a preflight request, at
+    /// most a day.
+    pub fn max_age(&mut self, max_age: Duration) -> &mut Self {
+        self.max_age_seconds = u32::try_from(max_age.as_secs()).unwrap_or(u32::MAX);
+        self
+    }
+
+    /// Checks the rule against the constraints B2 puts on it.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Err(Error::new(ErrorKind::BadRequest, message));
+
+        let name_ok = (6..=50).contains(&self.name.len())
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !self.name.starts_with("b2-");
+        if !name_ok {
+            return invalid(format!(
+                "CORS rule name `{}` must be 6 to 50 letters, digits and `-` and not start with `b2-`",
+                self.name
+            ));
+        }
+
+        if self.allowed_origins.is_empty() {
+            return invalid(format!("CORS rule `{}` allows no origins", self.name));
+        }
+        for origin in &self.allowed_origins {
+            let origin_ok = origin == "*"
+                || is_bare_scheme(origin)
+                || (origin.contains("://") && origin.matches('*').count() <= 1);
+            if !origin_ok {
+                return invalid(format!(
+                    "origin `{}` of CORS rule `{}` must be `*`, a scheme, or include the scheme with at most one `*`",
+                    origin, self.name
+                ));
+            }
+        }
+
+        if self.allowed_operations.is_empty() {
+            return invalid(format!("CORS rule `{}` allows no operations", self.name));
+        }
+        for operation in &self.allowed_operations {
+            if !CORS_OPERATIONS.contains(&operation.as_str()) {
+                return invalid(format!(
+                    "unknown operation `{}` in CORS rule `{}`",
+                    operation, self.name
+                ));
+            }
+        }
+
+        for header in self.allowed_headers.iter().flatten() {
+            if header.is_empty() || header.matches('*').count() > 1 {
+                return invalid(format!(
+                    "allowed header `{}` of CORS rule `{}` must be non-empty with at most one `*`",
+                    header, self.name
+                ));
+            }
+        }
+        for header in self.expose_headers.iter().flatten() {
+            if header.is_empty() || header.contains('*') {
+                return invalid(format!(
+                    "exposed header `{}` of CORS rule `{}` must be non-empty without `*`",
+                    header, self.name
+                ));
+            }
+        }
+
+        if self.max_age_seconds > MAX_AGE_SECONDS {
+            return invalid(format!(
+                "max age of CORS rule `{}` must be at most {} seconds",
+                self.name, MAX_AGE_SECONDS
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Whether a browser on `origin` may make `operation` calls sending the
+    /// given request `headers`.
+    pub fn allows<S: AsRef<str>>(&self, origin: &str, operation: &str, headers: &[S]) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|pattern| origin_matches(pattern, origin))
+            && self.allowed_operations.iter().any(|op| op == operation)
+            && headers.iter().all(|header| {
+                self.allowed_headers
+                    .iter()
+                    .flatten()
+                    .any(|pattern| wildcard_matches(pattern, header.as_ref()))
+            })
+    }
+}
+
+/// Checks a whole set of rules for a bucket, as B2 does on create and update.
+pub(crate) fn validate_cors_rules(rules: &[CorsRule]) -> Result<()> {
+    if rules.len() > MAX_CORS_RULES {
+        return Err(Error::new(
+            ErrorKind::BadRequest,
+            format!("a bucket can have at most {} CORS rules", MAX_CORS_RULES),
+        ));
+    }
+
+    let mut names = HashSet::new();
+    for rule in rules {
+        rule.validate()?;
+        if !names.insert(rule.name.as_str()) {
+            return Err(Error::new(
+                ErrorKind::BadRequest,
+                format!("duplicate CORS rule name `{}`", rule.name),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn origin_matches(pattern: &str, origin: &str) -> bool {
+    // A bare scheme stands for every origin using it.
+    if is_bare_scheme(pattern) {
+        return origin
+            .split_once("://")
+            .is_some_and(|(scheme, _)| scheme.eq_ignore_ascii_case(pattern));
+    }
+
+    wildcard_matches(pattern, origin)
+}
+
+/// Whether an origin pattern is just one of the schemes B2 accepts on its
+/// own, `https` or `http`.
+fn is_bare_scheme(pattern: &str) -> bool {
+    pattern.eq_ignore_ascii_case("https") || pattern.eq_ignore_ascii_case("http")
+}
+
+/// Case insensitive match of `value` against `pattern`, in which a `*`
+/// stands for any run of characters.
+fn wildcard_matches(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let value = value.to_ascii_lowercase();
+
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            value.len() >= prefix.len() + suffix.len()
+                && value.starts_with(prefix)
+                && value.ends_with(suffix)
+        }
+        None => pattern == value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::fails_with;
+
+    fn rule() -> CorsRule {
+        let mut rule = CorsRule::new("downloads");
+        rule.allowed_origin("https://www.example.com")
+            .allowed_operation("b2_download_file_by_name")
+            .max_age(Duration::from_secs(3600));
+        rule
+    }
+
+    #[test]
+    fn valid_rules() {
+        assert!(rule().validate().is_ok());
+
+        let mut rule = rule();
+        rule.allowed_origin("https://*.example.com")
+            .allowed_origin("https")
+            .allowed_origin("*")
+            .allowed_header("range")
+            .allowed_header("x-bz-info-*")
+            .expose_header("x-bz-content-sha1");
+        assert!(rule.validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_names() {
+        for name in ["short", "b2-reserved", "with space", &"x".repeat(51)] {
+            let rule = CorsRule {
+                name: name.to_string(),
+                ..rule()
+            };
+            assert!(
+                fails_with(rule.validate(), ErrorKind::BadRequest),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_origins() {
+        for origin in [
+            "",
+            "*.example.com",
+            "https://*.*.example.com",
+            "www.example.com",
+            "localhost",
+            "www",
+        ] {
+            let mut rule = rule();
+            rule.allowed_origin(origin);
+            assert!(
+                fails_with(rule.validate(), ErrorKind::BadRequest),
+                "{}",
+                origin
+            );
+        }
+
+        let rule = CorsRule {
+            allowed_origins: Vec::new(),
+            ..rule()
+        };
+        assert!(fails_with(rule.validate(), ErrorKind::BadRequest));
+    }
+
+    #[test]
+    fn invalid_operations_headers_and_max_age() {
+        let mut invalid = rule();
+        invalid.allowed_operation("b2_delete_file_version");
+        assert!(fails_with(invalid.validate(), ErrorKind::BadRequest));
+
+        let invalid = CorsRule {
+            allowed_operations: Vec::new(),
+            ..rule()
+        };
+        assert!(fails_with(invalid.validate(), ErrorKind::BadRequest));
+
+        let mut invalid = rule();
+        invalid.allowed_header("x-*-*");
+        assert!(fails_with(invalid.validate(), ErrorKind::BadRequest));
+
+        let mut invalid = rule();
+        invalid.expose_header("x-bz-*");
+        assert!(fails_with(invalid.validate(), ErrorKind::BadRequest));
+
+        let mut invalid = rule();
+        invalid.max_age(Duration::from_secs(86401));
+        assert!(fails_with(invalid.validate(), ErrorKind::BadRequest));
+    }
+
+    #[test]
+    fn too_many_or_duplicate_rules() {
+        let other = CorsRule {
+            name: "uploads".into(),
+            ..rule()
+        };
+        assert!(validate_cors_rules(&[rule(), other]).is_ok());
+        assert!(validate_cors_rules(&[rule(), rule()]).is_err());
+
+        let rules: Vec<_> = (0..=MAX_CORS_RULES)
+            .map(|i| CorsRule {
+                name: format!("rule-{}", i),
+                ..rule()
+            })
+            .collect();
+        assert!(validate_cors_rules(&rules[1..]).is_ok());
+        assert!(validate_cors_rules(&rules).is_err());
+    }
+
+    #[test]
+    fn allows_matching_origins() {
+        let mut rule = rule();
+        rule.allowed_origin("https://*.example.org");
+
+        assert!(rule.allows::<&str>("https://www.example.com", "b2_download_file_by_name", &[]));
+        assert!(rule.allows::<&str>("HTTPS://WWW.EXAMPLE.COM", "b2_download_file_by_name", &[]));
+        assert!(rule.allows::<&str>("https://a.example.org", "b2_download_file_by_name", &[]));
+        assert!(!rule.allows::<&str>("http://www.example.com", "b2_download_file_by_name", &[]));
+        assert!(!rule.allows::<&str>("https://example.net", "b2_download_file_by_name", &[]));
+    }
+
+    #[test]
+    fn allows_any_origin_or_scheme() {
+        let any = CorsRule {
+            allowed_origins: vec!["*".into()],
+            ..rule()
+        };
+        assert!(any.allows::<&str>("http://localhost:8080", "b2_download_file_by_name", &[]));
+
+        let https = CorsRule {
+            allowed_origins: vec!["https".into()],
+            ..rule()
+        };
+        assert!(https.allows::<&str>("https://anything.example", "b2_download_file_by_name", &[]));
+        assert!(!https.allows::<&str>("http://anything.example", "b2_download_file_by_name", &[]));
+    }
+
+    #[test]
+    fn host_without_scheme_is_not_a_scheme() {
+        let rule = CorsRule {
+            allowed_origins: vec!["localhost".into()],
+            ..rule()
+        };
+        assert!(!rule.allows::<&str>("localhost://x", "b2_download_file_by_name", &[]));
+        assert!(!rule.allows::<&str>("http://localhost", "b2_download_file_by_name", &[]));
+    }
+
+    #[test]
+    fn scheme_less_wildcard_is_not_a_scheme() {
+        // Rules read from B2 are not validated locally, so such a pattern
+        // can still turn up and is matched as a wildcard.
+        let rule = CorsRule {
+            allowed_origins: vec!["*.example.com".into()],
+            ..rule()
+        };
+        assert!(rule.allows::<&str>("https://a.example.com", "b2_download_file_by_name", &[]));
+        assert!(!rule.allows::<&str>("https://a.example.org", "b2_download_file_by_name", &[]));
+    }
+
+    #[test]
+    fn allows_only_listed_operations_and_headers() {
+        let mut rule = rule();
+        rule.allowed_header("range").allowed_header("x-bz-info-*");
+
+        assert!(!rule.allows::<&str>("https://www.example.com", "b2_upload_file", &[]));
+        assert!(rule.allows(
+            "https://www.example.com",
+            "b2_download_file_by_name",
+            &["Range", "X-Bz-Info-Author"]
+        ));
+        assert!(!rule.allows(
+            "https://www.example.com",
+            "b2_download_file_by_name",
+            &["authorization"]
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::{
+    validate_cors_rules, Bucket, BucketType, CorsRule, DefaultRetention, LifecycleRule,
+    ServerSideEncryption,
+};
+use crate::{Client, Result};
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateBucketRequest {
+    pub account_id: String,
+    bucket_name: String,
+    bucket_type: BucketType,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    bucket_info: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    cors_rules: Vec<CorsRule>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    lifecycle_rules: Vec<LifecycleRule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_server_side_encryption: Option<ServerSideEncryption>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    file_lock_enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_retention: Option<DefaultRetention>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CreateBucketBuilder {
+    inner: Client,
+    bucket_name: String,
+    bucket_type: BucketType,
+    bucket_info: HashMap<String, String>,
+    cors_rules: Vec<CorsRule>,
+    lifecycle_rules: Vec<LifecycleRule>,
+    default_server_side_encryption: Option<ServerSideEncryption>,
+    file_lock_enabled: bool,
+    default_retention: Option<DefaultRetention>,
+}
+
+impl CreateBucketBuilder {
+    pub(crate) fn new<T: AsRef<str>>(
+        client: Client,
+        bucket_name: T,
+        bucket_type: BucketType,
+    ) -> Self {
+        Self {
+            inner: client,
+            bucket_name: bucket_name.as_ref().to_string(),
+            bucket_type,
+            bucket_info: Default::default(),
+            cors_rules: Default::default(),
+            lifecycle_rules: Default::default(),
+            default_server_side_encryption: Default::default(),
+            file_lock_enabled: Default::default(),
+            default_retention: Default::default(),
+        }
+    }
+
+    /// Custom information stored with the bucket, e.g. `Cache-Control`
+    /// defaults for its files.
+    pub fn bucket_info(&mut self, bucket_info: HashMap<String, String>) -> &mut Self {
+        self.bucket_info = bucket_info;
+        self
+    }
+
+    pub fn cors_rules(&mut self, cors_rules: Vec<CorsRule>) -> &mut Self {
+        self.cors_rules = cors_rules;
+        self
+    }
+
+    pub fn lifecycle_rules(&mut self, lifecycle_rules: Vec<LifecycleRule>) -> &mut Self {
+        self.lifecycle_rules = lifecycle_rules;
+        self
+    }
+
+    /// Encryption of files uploaded without their own, such as
+    /// [`ServerSideEncryption::sse_b2`].
+    pub fn default_server_side_encryption(
+        &mut self,
+        default_server_side_encryption: ServerSideEncryption,
+    ) -> &mut Self {
+        self.default_server_side_encryption = Some(default_server_side_encryption);
+        self
+    }
+
+    /// Enables Object Lock for the bucket, which can only be done when the
+    /// bucket is created.
+    pub fn file_lock_enabled(&mut self, file_lock_enabled: bool) -> &mut Self {
+        self.file_lock_enabled = file_lock_enabled;
+        self
+    }
+
+    /// Retention of files uploaded without their own, requires Object Lock.
+    pub fn default_retention(&mut self, default_retention: DefaultRetention) -> &mut Self {
+        self.default_retention = Some(default_retention);
+        self
+    }
+
+    pub async fn send(&mut self) -> Result<Bucket> {
+        validate_cors_rules(&self.cors_rules)?;
+
+        let req = CreateBucketRequest {
+            account_id: Default::default(),
+            bucket_name: self.bucket_name.clone(),
+            bucket_type: self.bucket_type.clone(),
+            bucket_info: self.bucket_info.clone(),
+            cors_rules: self.cors_rules.clone(),
+            lifecycle_rules: self.lifecycle_rules.clone(),
+            default_server_side_encryption: self.default_server_side_encryption.clone(),
+            file_lock_enabled: self.file_lock_enabled,
+            default_retention: self.default_retention.clone(),
+        };
+
+        let res = self.inner._create_bucket(req).await?;
+
+        Ok(Bucket::from_list_buckets_buckets(self.inner.clone(), res))
+    }
+}
@@ -0,0 +1,294 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::file::{File, FileAction};
+
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+/// Hides and deletes files under a name prefix once they reach a given age.
+///
+/// When the prefixes of several rules match a file name, the longest one
+/// applies.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifecycleRule {
+    pub file_name_prefix: String,
+    pub days_from_uploading_to_hiding: Option<u32>,
+    pub days_from_hiding_to_deleting: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days_from_starting_to_canceling_unfinished_large_files: Option<u32>,
+}
+
+impl LifecycleRule {
+    /// A rule for all files whose name starts with `file_name_prefix`, an
+    /// empty prefix matches every file.
+    pub fn new<T: AsRef<str>>(file_name_prefix: T) -> Self {
+        Self {
+            file_name_prefix: file_name_prefix.as_ref().to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Hides the latest version of a file this many days after it was
+    /// uploaded.
+    pub fn hide_after_days(&mut self, days: u32) -> &mut Self {
+        self.days_from_uploading_to_hiding = Some(days);
+        self
+    }
+
+    /// Deletes a version this many days after it was hidden or replaced by
+    /// a newer version.
+    pub fn delete_after_days(&mut self, days: u32) -> &mut Self {
+        self.days_from_hiding_to_deleting = Some(days);
+        self
+    }
+
+    /// Cancels large files this many days after they were started, unless
+    /// they have been finished by then.
+    pub fn cancel_unfinished_after_days(&mut self, days: u32) -> &mut Self {
+        self.days_from_starting_to_canceling_unfinished_large_files = Some(days);
+        self
+    }
+}
+
+/// What a set of lifecycle rules would have done to a listing of file
+/// versions by a given time.
+///
+/// This only looks at the versions it is given, which should be every
+/// version of the affected files as listed by
+/// [`Bucket::list_file_versions`](super::Bucket::list_file_versions). Days
+/// are counted as 24 hours from the upload timestamps, while B2 runs the
+/// rules once a day, so files close to a boundary may be handled a day later.
+#[derive(Clone, Debug, Default)]
+pub struct LifecyclePreview {
+    /// Latest versions that would be hidden, but not yet deleted.
+    pub hidden: Vec<File>,
+    /// Versions that would be deleted, including hide markers.
+    pub deleted: Vec<File>,
+    /// Unfinished large files that would be cancelled.
+    pub canceled: Vec<File>,
+}
+
+impl LifecyclePreview {
+    pub fn evaluate(rules: &[LifecycleRule], versions: &[File], at: SystemTime) -> Self {
+        let at = at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX));
+        let mut preview = Self::default();
+
+        let mut by_name: BTreeMap<&str, Vec<&File>> = BTreeMap::new();
+        for file in versions {
+            let Some(rule) = rule_for(rules, &file.name) else {
+                continue;
+            };
+            match file.action {
+                FileAction::Start => {
+                    let canceled_at = after_days(
+                        file.upload_timestamp,
+                        rule.days_from_starting_to_canceling_unfinished_large_files,
+                    );
+                    if canceled_at.is_some_and(|t| t <= at) {
+                        preview.canceled.push(file.clone());
+                    }
+                }
+                FileAction::Upload | FileAction::Hide => {
+                    by_name.entry(&file.name).or_default().push(file);
+                }
+                _ => {}
+            }
+        }
+
+        for (name, mut versions) in by_name {
+            let Some(rule) = rule_for(rules, name) else {
+                continue;
+            };
+            versions.sort_by_key(|f| Reverse(f.upload_timestamp));
+
+            // Time the version before stopped being the latest one, which
+            // starts its countdown to deletion.
+            let mut replaced_at = None;
+            for file in versions {
+                let hidden_at = match (replaced_at, file.action) {
+                    (Some(t), _) => Some(t),
+                    (None, FileAction::Hide) => Some(file.upload_timestamp),
+                    (None, _) => {
+                        after_days(file.upload_timestamp, rule.days_from_uploading_to_hiding)
+                    }
+                };
+                let deleted_at =
+                    hidden_at.and_then(|t| after_days(t, rule.days_from_hiding_to_deleting));
+
+                if deleted_at.is_some_and(|t| t <= at) {
+                    preview.deleted.push(file.clone());
+                } else if replaced_at.is_none()
+                    && file.action == FileAction::Upload
+                    && hidden_at.is_some_and(|t| t <= at)
+                {
+                    preview.hidden.push(file.clone());
+                }
+
+                replaced_at = Some(file.upload_timestamp);
+            }
+        }
+
+        preview
+    }
+}
+
+/// The rule with the longest prefix matching `name`.
+fn rule_for<'a>(rules: &'a [LifecycleRule], name: &str) -> Option<&'a LifecycleRule> {
+    rules
+        .iter()
+        .filter(|r| name.starts_with(&r.file_name_prefix))
+        .max_by_key(|r| r.file_name_prefix.len())
+}
+
+fn after_days(timestamp: i64, days: Option<u32>) -> Option<i64> {
+    days.map(|d| timestamp.saturating_add(i64::from(d) * DAY_MILLIS))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::bucket::UploadFileResponse;
+
+    fn version(name: &str, action: FileAction, upload_timestamp: i64) -> File {
+        let res: UploadFileResponse = serde_json::from_value(serde_json::json!({
+            "accountId": "acc",
+            "bucketId": "bucket",
+            "contentLength": 0,
+            "fileId": format!("{}@{}", name, upload_timestamp),
+            "fileName": name,
+            "uploadTimestamp": upload_timestamp,
+        }))
+        .unwrap();
+
+        File {
+            action,
+            ..res.into()
+        }
+    }
+
+    fn at(millis: i64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_millis(millis as u64)
+    }
+
+    fn ids(files: &[File]) -> Vec<&str> {
+        files.iter().map(|f| f.id.as_str()).collect()
+    }
+
+    #[test]
+    fn hides_latest_version_after_days() {
+        let mut rule = LifecycleRule::new("");
+        rule.hide_after_days(7);
+        let versions = [version("a", FileAction::Upload, 0)];
+
+        let preview = LifecyclePreview::evaluate(&[rule.clone()], &versions, at(6 * DAY_MILLIS));
+        assert!(preview.hidden.is_empty());
+
+        let preview = LifecyclePreview::evaluate(&[rule], &versions, at(8 * DAY_MILLIS));
+        assert_eq!(ids(&preview.hidden), ["a@0"]);
+        assert!(preview.deleted.is_empty());
+    }
+
+    #[test]
+    fn boundary_day_is_included() {
+        let mut rule = LifecycleRule::new("");
+        rule.hide_after_days(1);
+        let versions = [version("a", FileAction::Upload, 1000)];
+
+        let preview =
+            LifecyclePreview::evaluate(&[rule.clone()], &versions, at(1000 + DAY_MILLIS - 1));
+        assert!(preview.hidden.is_empty());
+
+        let preview = LifecyclePreview::evaluate(&[rule], &versions, at(1000 + DAY_MILLIS));
+        assert_eq!(ids(&preview.hidden), ["a@1000"]);
+    }
+
+    #[test]
+    fn deletes_after_hiding() {
+        let mut rule = LifecycleRule::new("");
+        rule.hide_after_days(7).delete_after_days(3);
+        let versions = [version("a", FileAction::Upload, 0)];
+
+        let preview =
+            LifecyclePreview::evaluate(&[rule.clone()], &versions, at(10 * DAY_MILLIS - 1));
+        assert_eq!(ids(&preview.hidden), ["a@0"]);
+        assert!(preview.deleted.is_empty());
+
+        let preview = LifecyclePreview::evaluate(&[rule], &versions, at(10 * DAY_MILLIS));
+        assert!(preview.hidden.is_empty());
+        assert_eq!(ids(&preview.deleted), ["a@0"]);
+    }
+
+    #[test]
+    fn deletes_replaced_and_hidden_versions() {
+        let mut rule = LifecycleRule::new("");
+        rule.delete_after_days(2);
+        let versions = [
+            version("a", FileAction::Upload, 0),
+            version("a", FileAction::Upload, 5 * DAY_MILLIS),
+            version("b", FileAction::Upload, 0),
+            version("b", FileAction::Hide, DAY_MILLIS),
+        ];
+
+        let preview = LifecyclePreview::evaluate(&[rule.clone()], &versions, at(6 * DAY_MILLIS));
+        assert!(preview.hidden.is_empty());
+        // The hide marker of `b` is deleted along with the version it hides.
+        assert_eq!(ids(&preview.deleted), ["b@86400000", "b@0"]);
+
+        // The first version of `a` counts down from being replaced, while
+        // the latest one is neither hidden nor replaced.
+        let preview = LifecyclePreview::evaluate(&[rule], &versions, at(7 * DAY_MILLIS));
+        assert_eq!(ids(&preview.deleted), ["a@0", "b@86400000", "b@0"]);
+    }
+
+    #[test]
+    fn longest_matching_prefix_applies() {
+        let mut all = LifecycleRule::new("");
+        all.hide_after_days(30);
+        let mut logs = LifecycleRule::new("logs/");
+        logs.hide_after_days(1);
+        let rules = [all, logs];
+        let versions = [
+            version("logs/a", FileAction::Upload, 0),
+            version("logs", FileAction::Upload, 0),
+            version("photos/a", FileAction::Upload, 0),
+        ];
+
+        let preview = LifecyclePreview::evaluate(&rules, &versions, at(2 * DAY_MILLIS));
+        assert_eq!(ids(&preview.hidden), ["logs/a@0"]);
+
+        let preview = LifecyclePreview::evaluate(&rules, &versions, at(30 * DAY_MILLIS));
+        assert_eq!(ids(&preview.hidden), ["logs@0", "logs/a@0", "photos/a@0"]);
+    }
+
+    #[test]
+    fn files_without_a_rule_are_kept() {
+        let mut logs = LifecycleRule::new("logs/");
+        logs.hide_after_days(1).delete_after_days(1);
+        let versions = [version("photos/a", FileAction::Upload, 0)];
+
+        let preview = LifecyclePreview::evaluate(&[logs], &versions, at(100 * DAY_MILLIS));
+        assert!(preview.hidden.is_empty());
+        assert!(preview.deleted.is_empty());
+    }
+
+    #[test]
+    fn cancels_unfinished_large_files() {
+        let mut rule = LifecycleRule::new("");
+        rule.cancel_unfinished_after_days(1);
+        let versions = [
+            version("big", FileAction::Start, 0),
+            version("bigger", FileAction::Start, DAY_MILLIS),
+        ];
+
+        let preview = LifecyclePreview::evaluate(&[rule], &versions, at(DAY_MILLIS));
+        assert_eq!(ids(&preview.canceled), ["big@0"]);
+    }
+}
@@ -1,5 +1,11 @@
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::{
+    BucketType, CorsRule, FileLockConfiguration, LifecycleRule, Protected,
+    ReplicationConfiguration, ServerSideEncryption,
+};
 use crate::{Bucket, Client, Result};
 
 #[derive(Debug, Clone)]
@@ -7,7 +13,7 @@ pub struct ListBucketsBuilder {
     inner: Client,
     bucket_id: Option<String>,
     bucket_name: Option<String>,
-    _bucket_types: Option<()>,
+    bucket_types: Option<BucketTypes>,
 }
 
 impl ListBucketsBuilder {
@@ -16,7 +22,7 @@ impl ListBucketsBuilder {
             inner: client,
             bucket_id: Default::default(),
             bucket_name: Default::default(),
-            _bucket_types: Default::default(),
+            bucket_types: Default::default(),
         }
     }
 
@@ -30,10 +36,33 @@ impl ListBucketsBuilder {
         self
     }
 
+    /// Only lists buckets of the given types. Without any types, the types
+    /// B2 lists by default are listed.
+    pub fn bucket_types<I>(&mut self, bucket_types: I) -> &mut Self
+    where
+        I: IntoIterator<Item = BucketType>,
+    {
+        let bucket_types: Vec<_> = bucket_types.into_iter().collect();
+        self.bucket_types = (!bucket_types.is_empty()).then_some(BucketTypes::Only(bucket_types));
+        self
+    }
+
+    /// Lists buckets of every type, including those B2 leaves out when no
+    /// types are given.
+    pub fn all_bucket_types(&mut self) -> &mut Self {
+        self.bucket_types = Some(BucketTypes::All);
+        self
+    }
+
     pub async fn send(&mut self) -> Result<Vec<Bucket>> {
+        self.inner
+            .check_allowed(self.bucket_id.as_deref(), self.bucket_name.as_deref(), None)
+            .await?;
+
         let req = ListBucketsRequest {
             bucket_id: self.bucket_id.clone(),
             bucket_name: self.bucket_name.clone(),
+            bucket_types: self.bucket_types.clone(),
             ..Default::default()
         };
 
@@ -52,6 +81,23 @@ pub(crate) struct ListBucketsRequest {
     pub bucket_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bucket_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket_types: Option<BucketTypes>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum BucketTypes {
+    All,
+    Only(Vec<BucketType>),
+}
+
+impl Serialize for BucketTypes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Self::All => ["all"].serialize(serializer),
+            Self::Only(bucket_types) => bucket_types.serialize(serializer),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -65,4 +111,84 @@ pub(crate) struct ListBucketsBuckets {
     pub account_id: String,
     pub bucket_id: String,
     pub bucket_name: String,
+    pub bucket_type: BucketType,
+    #[serde(default)]
+    pub bucket_info: HashMap<String, String>,
+    pub revision: u64,
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub cors_rules: Vec<CorsRule>,
+    #[serde(default)]
+    pub lifecycle_rules: Vec<LifecycleRule>,
+    pub default_server_side_encryption: Option<Protected<ServerSideEncryption>>,
+    pub file_lock_configuration: Option<Protected<FileLockConfiguration>>,
+    pub replication_configuration: Option<Protected<ReplicationConfiguration>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::client;
+
+    fn request_json(builder: &ListBucketsBuilder) -> serde_json::Value {
+        let req = ListBucketsRequest {
+            bucket_types: builder.bucket_types.clone(),
+            ..Default::default()
+        };
+        serde_json::to_value(req).unwrap()
+    }
+
+    #[test]
+    fn bucket_types_are_serialized() {
+        let mut builder = ListBucketsBuilder::new(client());
+
+        assert!(request_json(&builder).get("bucketTypes").is_none());
+
+        builder.bucket_types([BucketType::AllPublic, BucketType::Snapshot]);
+        assert_eq!(
+            request_json(&builder)["bucketTypes"],
+            serde_json::json!(["allPublic", "snapshot"])
+        );
+
+        builder.all_bucket_types();
+        assert_eq!(
+            request_json(&builder)["bucketTypes"],
+            serde_json::json!(["all"])
+        );
+
+        builder.bucket_types([]);
+        assert!(request_json(&builder).get("bucketTypes").is_none());
+    }
+
+    #[test]
+    fn unknown_bucket_types_deserialize() {
+        let res: ListBucketsResponse = serde_json::from_str(
+            r#"{
+                "buckets": [
+                    {
+                        "accountId": "acc",
+                        "bucketId": "b1",
+                        "bucketName": "known",
+                        "bucketType": "allPrivate",
+                        "revision": 1
+                    },
+                    {
+                        "accountId": "acc",
+                        "bucketId": "b2",
+                        "bucketName": "unknown",
+                        "bucketType": "someFutureType",
+                        "revision": 1
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(res.buckets[0].bucket_type, BucketType::AllPrivate);
+        assert_eq!(
+            res.buckets[1].bucket_type,
+            BucketType::Other("someFutureType".into())
+        );
+    }
 }
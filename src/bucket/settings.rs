@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A bucket setting that B2 only returns to keys allowed to read it.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Protected<T> {
+    is_client_authorized_to_read: bool,
+    value: Option<T>,
+}
+
+impl<T> Protected<T> {
+    pub(crate) fn into_value(self) -> Option<T> {
+        self.value.filter(|_| self.is_client_authorized_to_read)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EncryptionMode {
+    /// Encrypted with keys managed by B2.
+    #[serde(rename = "SSE-B2")]
+    SseB2,
+    /// Encrypted with a key provided by the client on every request.
+    #[serde(rename = "SSE-C")]
+    SseC,
+}
+
+/// How files are encrypted at rest, a missing mode means not at all.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerSideEncryption {
+    pub mode: Option<EncryptionMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+}
+
+impl ServerSideEncryption {
+    /// SSE-B2 with AES256, the only setting B2 accepts as a bucket default
+    /// besides no encryption.
+    pub fn sse_b2() -> Self {
+        Self {
+            mode: Some(EncryptionMode::SseB2),
+            algorithm: Some("AES256".to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RetentionMode {
+    /// Can be shortened or removed by keys with the `bypassGovernance`
+    /// capability.
+    Governance,
+    /// Cannot be shortened or removed by anyone until it expires.
+    Compliance,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RetentionPeriodUnit {
+    Days,
+    Years,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPeriod {
+    pub duration: u32,
+    pub unit: RetentionPeriodUnit,
+}
+
+/// Retention applied to new files that are uploaded without one.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefaultRetention {
+    pub mode: Option<RetentionMode>,
+    pub period: Option<RetentionPeriod>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileLockConfiguration {
+    pub is_file_lock_enabled: bool,
+    #[serde(default)]
+    pub default_retention: DefaultRetention,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicationConfiguration {
+    pub as_replication_source: Option<ReplicationSource>,
+    pub as_replication_destination: Option<ReplicationDestination>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicationSource {
+    pub source_application_key_id: String,
+    pub replication_rules: Vec<ReplicationRule>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicationRule {
+    pub replication_rule_name: String,
+    pub destination_bucket_id: String,
+    pub file_name_prefix: String,
+    pub include_existing_files: bool,
+    pub is_enabled: bool,
+    pub priority: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicationDestination {
+    /// Keys of source accounts, mapped to the keys of this account used to
+    /// write their replicas.
+    pub source_to_destination_key_mapping: HashMap<String, String>,
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::{
+    validate_cors_rules, Bucket, BucketType, CorsRule, DefaultRetention, LifecycleRule,
+    ServerSideEncryption,
+};
+use crate::Result;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateBucketRequest {
+    pub account_id: String,
+    bucket_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bucket_type: Option<BucketType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bucket_info: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cors_rules: Option<Vec<CorsRule>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lifecycle_rules: Option<Vec<LifecycleRule>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_server_side_encryption: Option<ServerSideEncryption>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    file_lock_enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_retention: Option<DefaultRetention>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    if_revision_is: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeleteBucketRequest {
+    pub account_id: String,
+    pub bucket_id: String,
+}
+
+/// Changes the settings of a bucket, leaving those that are not set as they
+/// are.
+#[derive(Clone, Debug)]
+pub struct UpdateBucketBuilder {
+    bucket: Bucket,
+    bucket_type: Option<BucketType>,
+    bucket_info: Option<HashMap<String, String>>,
+    cors_rules: Option<Vec<CorsRule>>,
+    lifecycle_rules: Option<Vec<LifecycleRule>>,
+    default_server_side_encryption: Option<ServerSideEncryption>,
+    file_lock_enabled: bool,
+    default_retention: Option<DefaultRetention>,
+    if_revision_is: Option<u64>,
+}
+
+impl UpdateBucketBuilder {
+    pub(crate) fn new(bucket: Bucket) -> Self {
+        Self {
+            bucket,
+            bucket_type: Default::default(),
+            bucket_info: Default::default(),
+            cors_rules: Default::default(),
+            lifecycle_rules: Default::default(),
+            default_server_side_encryption: Default::default(),
+            file_lock_enabled: Default::default(),
+            default_retention: Default::default(),
+            if_revision_is: Default::default(),
+        }
+    }
+
+    pub fn bucket_type(&mut self, bucket_type: BucketType) -> &mut Self {
+        self.bucket_type = Some(bucket_type);
+        self
+    }
+
+    /// Replaces all custom information stored with the bucket.
+    pub fn bucket_info(&mut self, bucket_info: HashMap<String, String>) -> &mut Self {
+        self.bucket_info = Some(bucket_info);
+        self
+    }
+
+    /// Replaces all CORS rules of the bucket, an empty list removes them.
+    pub fn cors_rules(&mut self, cors_rules: Vec<CorsRule>) -> &mut Self {
+        self.cors_rules = Some(cors_rules);
+        self
+    }
+
+    /// Replaces all lifecycle rules of the bucket, an empty list removes
+    /// them.
+    pub fn lifecycle_rules(&mut self, lifecycle_rules: Vec<LifecycleRule>) -> &mut Self {
+        self.lifecycle_rules = Some(lifecycle_rules);
+        self
+    }
+
+    /// Encryption of files uploaded without their own, a default value
+    /// turns it off for new files.
+    pub fn default_server_side_encryption(
+        &mut self,
+        default_server_side_encryption: ServerSideEncryption,
+    ) -> &mut Self {
+        self.default_server_side_encryption = Some(default_server_side_encryption);
+        self
+    }
+
+    /// Enables Object Lock on an existing bucket, it cannot be disabled
+    /// again.
+    pub fn enable_file_lock(&mut self) -> &mut Self {
+        self.file_lock_enabled = true;
+        self
+    }
+
+    /// Retention of files uploaded without their own, requires Object Lock.
+    /// A default value removes it.
+    pub fn default_retention(&mut self, default_retention: DefaultRetention) -> &mut Self {
+        self.default_retention = Some(default_retention);
+        self
+    }
+
+    /// Only applies the update if the bucket is still at `revision`, failing
+    /// with [`ErrorKind::Conflict`](crate::ErrorKind::Conflict) if someone
+    /// else updated it in the meantime.
+    pub fn if_revision_is(&mut self, revision: u64) -> &mut Self {
+        self.if_revision_is = Some(revision);
+        self
+    }
+
+    /// Returns the updated bucket, which shares its upload configuration and
+    /// upload URLs with the one it was updated from.
+    pub async fn send(&mut self) -> Result<Bucket> {
+        self.bucket.check_allowed(None).await?;
+        if let Some(cors_rules) = &self.cors_rules {
+            validate_cors_rules(cors_rules)?;
+        }
+
+        let req = UpdateBucketRequest {
+            account_id: Default::default(),
+            bucket_id: self.bucket.id.clone(),
+            bucket_type: self.bucket_type.clone(),
+            bucket_info: self.bucket_info.clone(),
+            cors_rules: self.cors_rules.clone(),
+            lifecycle_rules: self.lifecycle_rules.clone(),
+            default_server_side_encryption: self.default_server_side_encryption.clone(),
+            file_lock_enabled: self.file_lock_enabled,
+            default_retention: self.default_retention.clone(),
+            if_revision_is: self.if_revision_is,
+        };
+
+        let res = self.bucket.client._update_bucket(req).await?;
+
+        Ok(self.bucket.updated(res))
+    }
+}
@@ -0,0 +1,869 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use futures_util::{stream, StreamExt, TryStreamExt};
+use reqwest::RequestBuilder;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::Bucket;
+use crate::account::StorageApiInfo;
+use crate::client::sha1_hex;
+use crate::error::ErrorKind;
+use crate::file::{
+    encode_file_name, Encryption, File, FileRetention, LargeFile, LegalHold, Part, UploadPartUrl,
+};
+use crate::{Error, Result};
+
+pub(super) const MAX_PARTS: u64 = 10_000;
+/// Largest part B2 accepts, for uploads and copies alike.
+pub(super) const MAX_PART_SIZE: u64 = 5_000_000_000;
+
+const MAX_FILE_INFO_ENTRIES: usize = 10;
+const MAX_FILE_INFO_KEY_LEN: usize = 50;
+/// Budget for the file name and file info header lines of an upload,
+/// counted as they are sent.
+const MAX_FILE_INFO_HEADER_BYTES: usize = 7000;
+/// File info keys B2 turns into response headers of downloads, other keys
+/// starting with `b2-` are reserved.
+pub(super) const B2_FILE_INFO_KEYS: &[&str] = &[
+    "b2-content-disposition",
+    "b2-content-language",
+    "b2-expires",
+    "b2-cache-control",
+    "b2-content-encoding",
+];
+
+/// Controls how [`Bucket::upload_file`] and
+/// [`Bucket::upload_file_from_reader`] split big uploads into parts.
+///
+/// Anything that does not fit in a single part is uploaded as a large file,
+/// with up to `parallelism` parts in flight at once. When a memory budget is
+/// set, the part size and the number of parts in flight are lowered so that
+/// the buffered parts stay within it. As the first two parts are read before
+/// any is sent, uploads fail if the budget cannot hold two parts.
+///
+/// Uploads that fit in a single part check out an upload URL each from a
+/// pool shared by all clones of a bucket, which holds at most
+/// `max_upload_urls` of them and makes further uploads wait for a free one.
+///
+/// Uploads are encrypted as set by `encryption`, or else by the default of
+/// the bucket.
+#[derive(Clone, Debug)]
+pub struct UploadConfig {
+    part_size: Option<u64>,
+    parallelism: usize,
+    memory_budget: Option<u64>,
+    max_upload_urls: usize,
+    encryption: Option<Encryption>,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            part_size: None,
+            parallelism: 4,
+            memory_budget: None,
+            max_upload_urls: 8,
+            encryption: None,
+        }
+    }
+}
+
+impl UploadConfig {
+    /// Size of each part, defaults to the recommended part size of the
+    /// account and always lies between the absolute minimum part size and
+    /// 5 GB.
+    pub fn part_size(&mut self, part_size: u64) -> &mut Self {
+        self.part_size = Some(part_size);
+        self
+    }
+
+    pub fn parallelism(&mut self, parallelism: usize) -> &mut Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Upper bound in bytes for the parts held in memory at once, at least
+    /// twice the absolute minimum part size of the account.
+    pub fn memory_budget(&mut self, memory_budget: u64) -> &mut Self {
+        self.memory_budget = Some(memory_budget);
+        self
+    }
+
+    /// Maximum number of single part uploads in flight at once for a bucket.
+    pub fn max_upload_urls(&mut self, max_upload_urls: usize) -> &mut Self {
+        self.max_upload_urls = max_upload_urls.max(1);
+        self
+    }
+
+    /// Server-side encryption of uploaded files and of copies made into the
+    /// bucket.
+    pub fn encryption(&mut self, encryption: Encryption) -> &mut Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    pub(super) fn upload_url_limit(&self) -> usize {
+        self.max_upload_urls
+    }
+
+    pub(super) fn upload_encryption(&self) -> Option<&Encryption> {
+        self.encryption.as_ref()
+    }
+
+    /// Adds the encryption headers of a single part upload.
+    pub(crate) fn apply_upload_headers(&self, mut req: RequestBuilder) -> RequestBuilder {
+        if let Some(encryption) = &self.encryption {
+            req = encryption.apply(req);
+        }
+        req
+    }
+
+    fn plan(&self, info: &StorageApiInfo, size_hint: Option<u64>) -> Result<PartPlan> {
+        let mut part_size = self.part_size.unwrap_or(info.recommended_part_size);
+        if let Some(budget) = self.memory_budget {
+            if budget < info.absolute_minimum_part_size * 2 {
+                return Err(Error::new(
+                    ErrorKind::BadRequest,
+                    format!(
+                        "memory budget of {} bytes cannot hold two parts of at least {} bytes",
+                        budget, info.absolute_minimum_part_size
+                    ),
+                ));
+            }
+            part_size = part_size.min(budget / 2);
+        }
+        if let Some(size) = size_hint {
+            part_size = part_size.max(size.div_ceil(MAX_PARTS));
+        }
+        part_size = part_size
+            .max(info.absolute_minimum_part_size)
+            .min(MAX_PART_SIZE);
+
+        self.plan_with_part_size(part_size)
+    }
+
+    /// Plan for a server-side copy of `size` bytes, where no part passes
+    /// through memory and the memory budget does not apply.
+    pub(super) fn copy_plan(&self, info: &StorageApiInfo, size: u64) -> PartPlan {
+        let part_size = self
+            .part_size
+            .unwrap_or(info.recommended_part_size)
+            .max(size.div_ceil(MAX_PARTS))
+            .max(info.absolute_minimum_part_size)
+            .min(MAX_PART_SIZE);
+
+        PartPlan {
+            part_size,
+            concurrency: self.parallelism,
+        }
+    }
+
+    /// Plan for parts of `part_size`, which the memory budget has to hold
+    /// twice over for the parts read ahead before the first one is sent.
+    fn plan_with_part_size(&self, part_size: u64) -> Result<PartPlan> {
+        let concurrency = match self.memory_budget {
+            Some(budget) if budget / part_size < 2 => {
+                return Err(Error::new(
+                    ErrorKind::BadRequest,
+                    format!(
+                        "memory budget of {} bytes cannot hold two parts of {} bytes",
+                        budget, part_size
+                    ),
+                ));
+            }
+            Some(budget) => usize::try_from(budget / part_size).unwrap_or(usize::MAX),
+            None => self.parallelism,
+        };
+
+        Ok(PartPlan {
+            part_size,
+            concurrency: concurrency.min(self.parallelism),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct PartPlan {
+    pub(super) part_size: u64,
+    pub(super) concurrency: usize,
+}
+
+/// Content type, file info and Object Lock settings of a single upload.
+///
+/// File info is stored with the file and returned as `X-Bz-Info-*` headers
+/// when it is downloaded. The `b2-*` keys set by the dedicated setters are
+/// returned as the corresponding standard headers instead.
+#[derive(Clone, Debug, Default)]
+pub struct UploadOptions {
+    content_type: Option<String>,
+    file_info: HashMap<String, String>,
+    file_retention: Option<FileRetention>,
+    legal_hold: Option<LegalHold>,
+}
+
+impl UploadOptions {
+    /// Content type of the file, defaults to `b2/x-auto`, which makes B2
+    /// pick one based on the file name.
+    pub fn content_type<T: AsRef<str>>(&mut self, content_type: T) -> &mut Self {
+        self.content_type = Some(content_type.as_ref().to_string());
+        self
+    }
+
+    /// Adds custom file info. Keys are case insensitive and stored in lower
+    /// case.
+    pub fn file_info<K, V>(&mut self, key: K, value: V) -> &mut Self
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        self.file_info.insert(
+            key.as_ref().to_ascii_lowercase(),
+            value.as_ref().to_string(),
+        );
+        self
+    }
+
+    /// Last modification time of the source of the upload, stored as
+    /// `src_last_modified_millis`.
+    pub fn src_last_modified(&mut self, src_last_modified: SystemTime) -> &mut Self {
+        let millis = src_last_modified
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis());
+        self.file_info("src_last_modified_millis", millis.to_string())
+    }
+
+    /// `Content-Disposition` returned when the file is downloaded.
+    pub fn content_disposition<T: AsRef<str>>(&mut self, content_disposition: T) -> &mut Self {
+        self.file_info("b2-content-disposition", content_disposition)
+    }
+
+    /// `Content-Language` returned when the file is downloaded.
+    pub fn content_language<T: AsRef<str>>(&mut self, content_language: T) -> &mut Self {
+        self.file_info("b2-content-language", content_language)
+    }
+
+    /// `Expires` returned when the file is downloaded, an HTTP date.
+    pub fn expires<T: AsRef<str>>(&mut self, expires: T) -> &mut Self {
+        self.file_info("b2-expires", expires)
+    }
+
+    /// `Cache-Control` returned when the file is downloaded.
+    pub fn cache_control<T: AsRef<str>>(&mut self, cache_control: T) -> &mut Self {
+        self.file_info("b2-cache-control", cache_control)
+    }
+
+    /// `Content-Encoding` returned when the file is downloaded.
+    pub fn content_encoding<T: AsRef<str>>(&mut self, content_encoding: T) -> &mut Self {
+        self.file_info("b2-content-encoding", content_encoding)
+    }
+
+    /// Object Lock retention of the file, instead of the default retention
+    /// of the bucket. Requires a bucket with Object Lock enabled.
+    pub fn file_retention(&mut self, file_retention: FileRetention) -> &mut Self {
+        self.file_retention = Some(file_retention);
+        self
+    }
+
+    /// Object Lock legal hold of the file. Requires a bucket with Object Lock
+    /// enabled.
+    pub fn legal_hold(&mut self, legal_hold: LegalHold) -> &mut Self {
+        self.legal_hold = Some(legal_hold);
+        self
+    }
+
+    pub(crate) fn content_type_or_auto(&self) -> &str {
+        self.content_type.as_deref().unwrap_or("b2/x-auto")
+    }
+
+    pub(crate) fn info(&self) -> &HashMap<String, String> {
+        &self.file_info
+    }
+
+    pub(crate) fn upload_file_retention(&self) -> Option<&FileRetention> {
+        self.file_retention.as_ref()
+    }
+
+    pub(crate) fn upload_legal_hold(&self) -> Option<LegalHold> {
+        self.legal_hold
+    }
+
+    /// Adds the content type, file info and Object Lock headers of a single
+    /// part upload.
+    pub(crate) fn apply(&self, mut req: RequestBuilder) -> RequestBuilder {
+        req = req.header(reqwest::header::CONTENT_TYPE, self.content_type_or_auto());
+        for (key, value) in &self.file_info {
+            req = req.header(format!("X-Bz-Info-{}", key), encode_file_name(value));
+        }
+        if let Some(file_retention) = &self.file_retention {
+            req = file_retention.apply(req);
+        }
+        if let Some(legal_hold) = self.legal_hold {
+            req = legal_hold.apply(req);
+        }
+        req
+    }
+
+    /// Checks the file info of an upload of `name` against the limits of B2.
+    fn validate(&self, name: &str) -> Result<()> {
+        let invalid = |message: String| Err(Error::new(ErrorKind::BadRequest, message));
+
+        if self.file_info.len() > MAX_FILE_INFO_ENTRIES {
+            return invalid(format!(
+                "at most {} file info entries are allowed",
+                MAX_FILE_INFO_ENTRIES
+            ));
+        }
+
+        let mut header_bytes = header_line_len("X-Bz-File-Name", &encode_file_name(name));
+        for (key, value) in &self.file_info {
+            let key_ok = (1..=MAX_FILE_INFO_KEY_LEN).contains(&key.len())
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !key_ok {
+                return invalid(format!(
+                    "file info key `{}` must be 1 to {} letters, digits, `-` and `_`",
+                    key, MAX_FILE_INFO_KEY_LEN
+                ));
+            }
+            if key.starts_with("b2-") && !B2_FILE_INFO_KEYS.contains(&key.as_str()) {
+                return invalid(format!("file info key `{}` is reserved", key));
+            }
+            header_bytes +=
+                header_line_len(&format!("X-Bz-Info-{}", key), &encode_file_name(value));
+        }
+        if header_bytes > MAX_FILE_INFO_HEADER_BYTES {
+            return invalid(format!(
+                "file name and file info take {} bytes of headers, at most {} are allowed",
+                header_bytes, MAX_FILE_INFO_HEADER_BYTES
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn header_line_len(name: &str, value: &str) -> usize {
+    // `<name>: <value>\r\n`
+    name.len() + value.len() + 4
+}
+
+impl Bucket {
+    /// Uploads everything `reader` yields, as a single file if it fits in one
+    /// part and as a large file otherwise.
+    ///
+    /// With `resume` set, an unfinished large file with the same name,
+    /// content type and file info is continued instead of starting a new
+    /// one, and the large file is left unfinished on failure so that it can
+    /// be resumed later. This is only sound when `size_hint` is the exact
+    /// size of the data.
+    pub(super) async fn upload<R>(
+        &self,
+        mut reader: R,
+        name: String,
+        size_hint: Option<u64>,
+        options: &UploadOptions,
+        resume: bool,
+    ) -> Result<File>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.check_allowed(Some(&name)).await?;
+        options.validate(&name)?;
+
+        let info = self.client.storage_api_info().await?;
+        let mut plan = self.upload_config.plan(&info, size_hint)?;
+
+        let mut resumed = None;
+        let mut existing = BTreeMap::new();
+        if let Some(size) = size_hint.filter(|&size| resume && size > plan.part_size) {
+            let found = self
+                .find_resumable(
+                    &name,
+                    options.content_type_or_auto(),
+                    options.info(),
+                    size,
+                    info.absolute_minimum_part_size,
+                )
+                .await?;
+            if let Some((file, parts, part_size)) = found {
+                tracing::trace!(
+                    file_id = file.id(),
+                    parts = parts.len(),
+                    "resuming unfinished large file"
+                );
+                plan = self.upload_config.plan_with_part_size(part_size)?;
+                existing = parts;
+                resumed = Some(file);
+            }
+        }
+
+        // A large file needs at least two parts, so only commit to one once
+        // there is data beyond the first part.
+        let first = read_part(&mut reader, plan.part_size).await?;
+        let second = read_part(&mut reader, plan.part_size).await?;
+        if second.is_empty() {
+            return self.upload_single(name, first, options).await;
+        }
+
+        let file = match resumed {
+            Some(file) => file,
+            None => {
+                self.start_large_file_with_info(
+                    &name,
+                    options.content_type_or_auto(),
+                    options.info().clone(),
+                    self.upload_config.upload_encryption(),
+                    options.upload_file_retention(),
+                    options.upload_legal_hold(),
+                )
+                .await?
+            }
+        };
+        tracing::trace!(
+            file_id = file.id(),
+            part_size = plan.part_size,
+            concurrency = plan.concurrency,
+            "uploading large file"
+        );
+
+        let rest = stream::try_unfold(reader, |mut reader| async move {
+            let part = read_part(&mut reader, plan.part_size).await?;
+            if part.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some((part, reader)))
+            }
+        });
+        let parts = stream::iter([Ok(first), Ok(second)]).chain(rest);
+
+        match self
+            .upload_parts(&file, parts, &existing, plan.concurrency)
+            .await
+        {
+            Ok(()) => self.finish_large_file(file).await,
+            Err(err) if resume => Err(err),
+            Err(err) => {
+                if let Err(cancel_err) = self.cancel_large_file(file).await {
+                    tracing::warn!("could not cancel large file: {}", cancel_err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Uploads `parts` in order, skipping those already present in
+    /// `existing` with the same SHA1.
+    async fn upload_parts<S>(
+        &self,
+        file: &LargeFile,
+        parts: S,
+        existing: &BTreeMap<u16, Part>,
+        concurrency: usize,
+    ) -> Result<()>
+    where
+        S: futures_util::Stream<Item = Result<Bytes>>,
+    {
+        // Every part in flight needs an upload URL of its own, idle ones are
+        // kept here for the next part.
+        let urls: Mutex<Vec<UploadPartUrl>> = Mutex::new(Vec::new());
+        let urls = &urls;
+
+        parts
+            .enumerate()
+            .map(|(i, part)| async move {
+                let part = part?;
+                let part_number = u16::try_from(i + 1)
+                    .ok()
+                    .filter(|&n| u64::from(n) <= MAX_PARTS)
+                    .ok_or_else(|| Error::new(ErrorKind::BadRequest, "too many parts"))?;
+
+                if let Some(uploaded) = existing.get(&part_number) {
+                    if uploaded.size == part.len() as u64 && uploaded.sha1 == sha1_hex(&part) {
+                        file.add_part(uploaded.clone());
+                        return Ok(());
+                    }
+                }
+
+                // A URL that failed is dropped rather than put back, so a
+                // retry always gets a different one.
+                self.client
+                    .retry_policy()
+                    .retry_when(
+                        || async {
+                            let idle = urls.lock().unwrap().pop();
+                            let url = match idle {
+                                Some(url) => url,
+                                None => self.get_upload_part_url_once(file).await?,
+                            };
+                            self.upload_part(file, &url, part_number, part.clone())
+                                .await?;
+                            urls.lock().unwrap().push(url);
+
+                            Ok(())
+                        },
+                        Error::is_retryable_upload,
+                    )
+                    .await
+            })
+            .buffer_unordered(concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Looks for the most recent unfinished large file named `name`, with
+    /// the same content type and file info, whose parts are consistent with
+    /// uploading `size` bytes, returning it with its parts and part size.
+    async fn find_resumable(
+        &self,
+        name: &str,
+        content_type: &str,
+        file_info: &HashMap<String, String>,
+        size: u64,
+        minimum_part_size: u64,
+    ) -> Result<Option<(LargeFile, BTreeMap<u16, Part>, u64)>> {
+        let mut candidates = Vec::new();
+        let mut start_file_id = None;
+        loop {
+            let mut builder = self.list_unfinished_large_files();
+            builder.name_prefix(name);
+            if let Some(id) = &start_file_id {
+                builder.start_file_id(id);
+            }
+            let (files, next_file_id) = builder.send().await?;
+            candidates.extend(files.into_iter().filter(|f| {
+                f.name() == name && f.content_type() == content_type && f.info() == file_info
+            }));
+
+            match next_file_id {
+                Some(id) => start_file_id = Some(id),
+                None => break,
+            }
+        }
+        candidates.sort_by_key(|f| Reverse(f.upload_timestamp()));
+
+        for file in candidates {
+            let parts = self.list_all_parts(&file).await?;
+            if let Some(part_size) = resumable_part_size(&parts, size, minimum_part_size) {
+                return Ok(Some((file, parts, part_size)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn list_all_parts(&self, file: &LargeFile) -> Result<BTreeMap<u16, Part>> {
+        let mut all = BTreeMap::new();
+        let mut start_part_number = None;
+        loop {
+            let mut builder = self.list_parts(file);
+            if let Some(n) = start_part_number {
+                builder.start_part_number(n);
+            }
+            let (parts, next_part_number) = builder.send().await?;
+            all.extend(parts.into_iter().map(|p| (p.number, p)));
+
+            match next_part_number {
+                Some(n) => start_part_number = Some(n),
+                None => break,
+            }
+        }
+
+        Ok(all)
+    }
+}
+
+/// Part size the already uploaded `parts` were cut with, provided that they
+/// all fit the layout of a `size` bytes upload.
+fn resumable_part_size(parts: &BTreeMap<u16, Part>, size: u64, minimum: u64) -> Option<u64> {
+    let part_size = parts.values().map(|p| p.size).max()?;
+    if part_size < minimum {
+        return None;
+    }
+
+    let count = size.div_ceil(part_size);
+    let fits = parts.values().all(|p| {
+        let n = u64::from(p.number);
+        let expected = if n < count {
+            part_size
+        } else {
+            size - (count - 1) * part_size
+        };
+        n >= 1 && n <= count && p.size == expected
+    });
+
+    (fits && count >= 2).then_some(part_size)
+}
+
+async fn read_part<R>(reader: &mut R, part_size: u64) -> Result<Bytes>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut buf = Vec::new();
+    (&mut *reader).take(part_size).read_to_end(&mut buf).await?;
+
+    Ok(buf.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::fails_with;
+
+    const MB: u64 = 1_000_000;
+
+    fn storage_api_info() -> StorageApiInfo {
+        StorageApiInfo {
+            url: "https://api.example.com".into(),
+            download_url: "https://f000.example.com".into(),
+            s3_api_url: "https://s3.example.com".into(),
+            recommended_part_size: 100 * MB,
+            absolute_minimum_part_size: 5 * MB,
+        }
+    }
+
+    fn parts(sizes: &[(u16, u64)]) -> BTreeMap<u16, Part> {
+        sizes
+            .iter()
+            .map(|&(number, size)| {
+                let part = Part {
+                    number,
+                    sha1: String::new(),
+                    size,
+                };
+                (number, part)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resumable_part_size_of_matching_parts() {
+        let existing = parts(&[(1, 100), (2, 100)]);
+        assert_eq!(resumable_part_size(&existing, 350, 50), Some(100));
+
+        let existing = parts(&[(1, 100), (4, 50)]);
+        assert_eq!(resumable_part_size(&existing, 350, 50), Some(100));
+    }
+
+    #[test]
+    fn resumable_part_size_rejects_other_layouts() {
+        assert_eq!(resumable_part_size(&parts(&[]), 350, 50), None);
+        // Smaller than the minimum part size.
+        assert_eq!(resumable_part_size(&parts(&[(1, 40)]), 350, 50), None);
+        // A part beyond the end of the upload.
+        assert_eq!(
+            resumable_part_size(&parts(&[(1, 100), (5, 100)]), 350, 50),
+            None
+        );
+        // The last part has the wrong size.
+        assert_eq!(
+            resumable_part_size(&parts(&[(1, 100), (4, 100)]), 350, 50),
+            None
+        );
+        // An upload that fits in a single part is no large file.
+        assert_eq!(resumable_part_size(&parts(&[(1, 100)]), 100, 50), None);
+    }
+
+    #[test]
+    fn plan_defaults_to_recommended_part_size() {
+        let plan = UploadConfig::default()
+            .plan(&storage_api_info(), None)
+            .unwrap();
+        assert_eq!(plan.part_size, 100 * MB);
+        assert_eq!(plan.concurrency, 4);
+    }
+
+    #[test]
+    fn plan_keeps_part_size_within_limits() {
+        let info = storage_api_info();
+
+        let plan = UploadConfig::default()
+            .part_size(MB)
+            .plan(&info, None)
+            .unwrap();
+        assert_eq!(plan.part_size, 5 * MB);
+
+        let plan = UploadConfig::default()
+            .part_size(10_000 * MB)
+            .plan(&info, None)
+            .unwrap();
+        assert_eq!(plan.part_size, MAX_PART_SIZE);
+    }
+
+    #[test]
+    fn plan_grows_parts_to_fit_max_parts() {
+        let plan = UploadConfig::default()
+            .plan(&storage_api_info(), Some(2_000_000 * MB))
+            .unwrap();
+        assert_eq!(plan.part_size, 200 * MB);
+    }
+
+    #[test]
+    fn plan_stays_within_memory_budget() {
+        let info = storage_api_info();
+
+        let plan = UploadConfig::default()
+            .memory_budget(50 * MB)
+            .plan(&info, None)
+            .unwrap();
+        assert_eq!(plan.part_size, 25 * MB);
+        assert_eq!(plan.concurrency, 2);
+
+        let plan = UploadConfig::default()
+            .part_size(10 * MB)
+            .memory_budget(35 * MB)
+            .plan(&info, None)
+            .unwrap();
+        assert_eq!(plan.part_size, 10 * MB);
+        assert_eq!(plan.concurrency, 3);
+
+        let plan = UploadConfig::default()
+            .part_size(5 * MB)
+            .parallelism(1)
+            .memory_budget(10 * MB)
+            .plan(&info, None)
+            .unwrap();
+        assert_eq!(plan.part_size, 5 * MB);
+        assert_eq!(plan.concurrency, 1);
+    }
+
+    #[test]
+    fn copy_plan_ignores_memory_budget() {
+        let plan = UploadConfig::default()
+            .memory_budget(10 * MB)
+            .copy_plan(&storage_api_info(), 10_000 * MB);
+        assert_eq!(plan.part_size, 100 * MB);
+        assert_eq!(plan.concurrency, 4);
+    }
+
+    #[test]
+    fn copy_plan_keeps_part_size_within_limits() {
+        let info = storage_api_info();
+
+        let plan = UploadConfig::default()
+            .part_size(MB)
+            .copy_plan(&info, 500 * MB);
+        assert_eq!(plan.part_size, 5 * MB);
+
+        let plan = UploadConfig::default().copy_plan(&info, 3_000_000 * MB);
+        assert_eq!(plan.part_size, 300 * MB);
+
+        let plan = UploadConfig::default()
+            .part_size(10_000 * MB)
+            .copy_plan(&info, 100_000 * MB);
+        assert_eq!(plan.part_size, MAX_PART_SIZE);
+    }
+
+    #[test]
+    fn plan_rejects_budget_below_two_parts() {
+        let info = storage_api_info();
+
+        assert!(fails_with(
+            UploadConfig::default()
+                .memory_budget(10 * MB - 1)
+                .plan(&info, None),
+            ErrorKind::BadRequest
+        ));
+
+        // Parts grown to stay within `MAX_PARTS` no longer fit twice.
+        assert!(fails_with(
+            UploadConfig::default()
+                .memory_budget(20 * MB)
+                .plan(&info, Some(200_000 * MB)),
+            ErrorKind::BadRequest
+        ));
+
+        assert!(fails_with(
+            UploadConfig::default()
+                .memory_budget(20 * MB)
+                .plan_with_part_size(15 * MB),
+            ErrorKind::BadRequest
+        ));
+    }
+
+    #[test]
+    fn file_info_entries_are_limited() {
+        let mut options = UploadOptions::default();
+        for i in 0..MAX_FILE_INFO_ENTRIES {
+            options.file_info(format!("key-{}", i), "value");
+        }
+        assert!(options.validate("a").is_ok());
+
+        options.file_info("one-more", "value");
+        assert!(fails_with(options.validate("a"), ErrorKind::BadRequest));
+    }
+
+    #[test]
+    fn file_info_keys_are_validated() {
+        let validate = |key: &str| {
+            let mut options = UploadOptions::default();
+            options.file_info(key, "value");
+            options.validate("a")
+        };
+
+        assert!(validate("src_last_modified_millis").is_ok());
+        assert!(validate("Camera-Model").is_ok());
+        assert!(validate(&"k".repeat(MAX_FILE_INFO_KEY_LEN)).is_ok());
+        assert!(fails_with(validate(""), ErrorKind::BadRequest));
+        assert!(fails_with(
+            validate(&"k".repeat(MAX_FILE_INFO_KEY_LEN + 1)),
+            ErrorKind::BadRequest
+        ));
+        assert!(fails_with(validate("camera model"), ErrorKind::BadRequest));
+        assert!(fails_with(validate("café"), ErrorKind::BadRequest));
+    }
+
+    #[test]
+    fn reserved_file_info_keys_are_rejected() {
+        let mut options = UploadOptions::default();
+        options
+            .content_disposition("attachment")
+            .cache_control("max-age=3600")
+            .content_encoding("gzip");
+        assert!(options.validate("a").is_ok());
+
+        options.file_info("b2-custom", "value");
+        assert!(fails_with(options.validate("a"), ErrorKind::BadRequest));
+    }
+
+    #[test]
+    fn file_info_headers_fit_budget() {
+        // `X-Bz-File-Name: a\r\n` and `X-Bz-Info-k: <value>\r\n`.
+        let fixed = header_line_len("X-Bz-File-Name", "a") + header_line_len("X-Bz-Info-k", "");
+        let validate = |value: &str| {
+            let mut options = UploadOptions::default();
+            options.file_info("k", value);
+            options.validate("a")
+        };
+
+        let longest = MAX_FILE_INFO_HEADER_BYTES - fixed;
+        assert!(validate(&"v".repeat(longest)).is_ok());
+        assert!(fails_with(
+            validate(&"v".repeat(longest + 1)),
+            ErrorKind::BadRequest
+        ));
+
+        // Values are counted percent-encoded, a space takes three bytes.
+        assert!(validate(&" ".repeat(longest / 3)).is_ok());
+        assert!(fails_with(
+            validate(&" ".repeat(longest / 3 + 1)),
+            ErrorKind::BadRequest
+        ));
+    }
+
+    #[test]
+    fn file_name_counts_against_header_budget() {
+        let options = UploadOptions::default();
+        let longest = MAX_FILE_INFO_HEADER_BYTES - header_line_len("X-Bz-File-Name", "");
+
+        assert!(options.validate(&"n".repeat(longest)).is_ok());
+        assert!(fails_with(
+            options.validate(&"n".repeat(longest + 1)),
+            ErrorKind::BadRequest
+        ));
+    }
+}
@@ -1,26 +1,44 @@
-use std::path::Path;
+use std::time::Duration;
 
-use serde::de::DeserializeOwned;
-use serde::Deserialize;
+use bytes::Bytes;
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
-use tokio::io::{AsyncRead, AsyncReadExt};
 
-use crate::account::{Authorized, StorageApiInfo};
+use crate::account::{Allowed, Authorized, StorageApiInfo};
 use crate::bucket::{
-    GetUploadUrlResponse, ListBucketsBuilder, ListBucketsRequest, ListBucketsResponse,
-    UploadFileResponse,
+    BucketType, CopyFileRequest, CopyPartRequest, CreateBucketBuilder, CreateBucketRequest,
+    DeleteBucketRequest, GetDownloadAuthorizationRequest, GetDownloadAuthorizationResponse,
+    GetUploadUrlResponse, ListBucketsBuckets, ListBucketsBuilder, ListBucketsRequest,
+    ListBucketsResponse, UpdateBucketRequest, UploadConfig, UploadFileResponse, UploadOptions,
 };
-use crate::error::ErrorResponse;
-use crate::file::{ListFileNamesRequest, ListFileNamesResponse};
-use crate::{Account, Bucket, Result};
+use crate::error::{ErrorKind, ErrorResponse};
+use crate::file::{
+    encode_file_name, file_url, CancelLargeFileRequest, CustomerKey, DeleteFileVersionRequest,
+    DeleteFileVersionResponse, DownloadFileBuilder, DownloadFileRequest, DownloadTarget,
+    FinishLargeFileRequest, GetUploadPartUrlRequest, GetUploadPartUrlResponse, HideFileRequest,
+    LargeFileResponse, ListFileNamesRequest, ListFileNamesResponse, ListFileVersionsRequest,
+    ListFileVersionsResponse, ListPartsRequest, ListPartsResponse, ListUnfinishedLargeFilesRequest,
+    ListUnfinishedLargeFilesResponse, RemoteFile, StartLargeFileRequest, UnhideFileRequest,
+    UpdateFileLegalHoldRequest, UpdateFileRetentionRequest, UpdateFileRetentionResponse,
+    UploadPartResponse,
+};
+use crate::key::{
+    Capability, CreateKeyBuilder, CreateKeyRequest, CreatedKey, DeleteKeyRequest, Key,
+    ListKeysBuilder, ListKeysRequest, ListKeysResponse,
+};
+use crate::{Account, AccountInfo, Bucket, Error, Result, RetryPolicy};
 
-pub const BASE_URL: &str = "https://api.backblazeb2.com";
+mod builder;
+
+pub use self::builder::ClientBuilder;
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct AuthorizeAccountResponse {
     account_id: String,
     api_info: AuthorizeAccountApiInfo,
+    application_key_expiration_timestamp: Option<i64>,
     #[serde(rename(deserialize = "authorizationToken"))]
     token: String,
 }
@@ -32,17 +50,27 @@ struct AuthorizeAccountApiInfo {
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct AuthorizeAccountApiInfoStorageApi {
     #[serde(rename(deserialize = "apiUrl"))]
     url: String,
     #[serde(rename(deserialize = "downloadUrl"))]
     download_url: String,
+    s3_api_url: String,
+    recommended_part_size: u64,
+    absolute_minimum_part_size: u64,
+    #[serde(flatten)]
+    allowed: Allowed,
 }
 
 #[derive(Clone, Debug)]
 pub struct Client {
     inner: reqwest::Client,
     account: Account,
+    retry_policy: RetryPolicy,
+    api_url: String,
+    user_agent: String,
+    timeout: Option<Duration>,
 }
 
 impl Client {
@@ -50,22 +78,132 @@ impl Client {
         Self {
             inner: reqwest::Client::new(),
             account: Account::new(id, secret),
+            retry_policy: Default::default(),
+            api_url: builder::DEFAULT_API_URL.to_string(),
+            user_agent: builder::DEFAULT_USER_AGENT.to_string(),
+            timeout: None,
+        }
+    }
+
+    /// Starts configuring a client, e.g. to use another API realm, timeouts
+    /// or a proxy.
+    pub fn builder(id: String, secret: String) -> ClientBuilder {
+        ClientBuilder::new(id, secret)
+    }
+
+    /// Every request goes through here so that it carries the configured
+    /// user agent and timeout, also on an HTTP client provided by the user.
+    fn request<U: reqwest::IntoUrl>(
+        &self,
+        method: reqwest::Method,
+        url: U,
+    ) -> reqwest::RequestBuilder {
+        let mut builder = self
+            .inner
+            .request(method, url)
+            .header(reqwest::header::USER_AGENT, &self.user_agent);
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
         }
+
+        builder
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Replaces the policy used to retry failed calls, see [`RetryPolicy`].
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// The current authorization, authorizing first if needed and retrying
+    /// that according to the retry policy.
+    async fn authorized(&self) -> Result<Authorized> {
+        self.retry_policy
+            .retry(|| self.get_or_try_authorize())
+            .await
     }
 
     async fn get_or_try_authorize(&self) -> Result<Authorized> {
         if let Some(authorized) = self.account.authorized() {
-            Ok(authorized)
-        } else {
-            self.authorize_account().await
+            return Ok(authorized);
+        }
+
+        let _guard = self.account.lock_authorization().await;
+        match self.account.authorized() {
+            Some(authorized) => Ok(authorized),
+            None => self.authorize_account().await,
+        }
+    }
+
+    /// Authorizes the account again after `stale` was rejected, unless a
+    /// concurrent caller already did so.
+    async fn reauthorize(&self, stale: &Authorized) -> Result<Authorized> {
+        let _guard = self.account.lock_authorization().await;
+        match self.account.authorized() {
+            Some(authorized) if authorized.token != stale.token => Ok(authorized),
+            _ => {
+                tracing::debug!("authorization token was rejected, authorizing again");
+                self.authorize_account().await
+            }
+        }
+    }
+
+    /// Sends the request built by `f` with the account authorization token,
+    /// replaying it once with a fresh token if B2 reports it as expired or
+    /// invalid, and retrying transient failures according to the retry
+    /// policy.
+    ///
+    /// Authorizing is part of each attempt, so that it is retried along with
+    /// the request rather than on its own.
+    async fn send_authorized<F>(&self, f: F) -> Result<reqwest::Response>
+    where
+        F: Fn(&Authorized) -> reqwest::RequestBuilder,
+    {
+        self.send_authorized_when(f, Error::is_retryable).await
+    }
+
+    /// Like [`Client::send_authorized`], with `retryable` deciding which
+    /// errors are worth another attempt.
+    async fn send_authorized_when<F, P>(&self, f: F, retryable: P) -> Result<reqwest::Response>
+    where
+        F: Fn(&Authorized) -> reqwest::RequestBuilder,
+        P: Fn(&Error) -> bool,
+    {
+        self.retry_policy
+            .retry_when(|| self.send_authorized_once(&f), retryable)
+            .await
+    }
+
+    async fn send_authorized_once<F>(&self, f: &F) -> Result<reqwest::Response>
+    where
+        F: Fn(&Authorized) -> reqwest::RequestBuilder,
+    {
+        let authorized = self.get_or_try_authorize().await?;
+        let req = f(&authorized).header(reqwest::header::AUTHORIZATION, &authorized.token);
+
+        match check_authorized_response(req.send().await?).await {
+            Err(err) if err.is_rejected_auth_token() => {
+                let authorized = self.reauthorize(&authorized).await?;
+                let req = f(&authorized).header(reqwest::header::AUTHORIZATION, &authorized.token);
+
+                // A bare 401 with a fresh token means the key lacks access.
+                check_b2_api_response(req.send().await?).await
+            }
+            res => res,
         }
     }
 
     async fn authorize_account(&self) -> Result<Authorized> {
         const PATH: &str = "/b2api/v3/b2_authorize_account";
-        let url = format!("{}{}", BASE_URL, PATH);
+        let url = format!("{}{}", self.api_url, PATH);
         let key = self.account.application_key();
-        let req = self.inner.get(url).basic_auth(key.id, Some(key.secret));
+        let req = self
+            .request(reqwest::Method::GET, url)
+            .basic_auth(key.id, Some(key.secret));
 
         let res = req.send().await?;
 
@@ -76,7 +214,12 @@ impl Client {
             storage_api_info: StorageApiInfo {
                 url: res.api_info.storage_api.url,
                 download_url: res.api_info.storage_api.download_url,
+                s3_api_url: res.api_info.storage_api.s3_api_url,
+                recommended_part_size: res.api_info.storage_api.recommended_part_size,
+                absolute_minimum_part_size: res.api_info.storage_api.absolute_minimum_part_size,
             },
+            allowed: res.api_info.storage_api.allowed,
+            application_key_expiration_timestamp: res.application_key_expiration_timestamp,
             token: res.token,
         };
 
@@ -85,104 +228,342 @@ impl Client {
         Ok(authorized)
     }
 
+    /// The capabilities and restrictions of the application key and the API
+    /// endpoints of the account, authorizing first if needed.
+    pub async fn account_info(&self) -> Result<AccountInfo> {
+        self.authorized()
+            .await
+            .map(|authorized| authorized.account_info())
+    }
+
+    /// Fails fast if the application key is restricted to other buckets or
+    /// file names, see [`Allowed::check`].
+    pub(crate) async fn check_allowed(
+        &self,
+        bucket_id: Option<&str>,
+        bucket_name: Option<&str>,
+        file_name: Option<&str>,
+    ) -> Result<()> {
+        self.authorized()
+            .await?
+            .allowed
+            .check(bucket_id, bucket_name, file_name)
+    }
+
+    pub(crate) async fn account_id(&self) -> Result<String> {
+        self.authorized().await.map(|authorized| authorized.id)
+    }
+
+    pub(crate) async fn storage_api_info(&self) -> Result<StorageApiInfo> {
+        self.authorized()
+            .await
+            .map(|authorized| authorized.storage_api_info)
+    }
+
+    /// Sent only once, uploads retry it along with the upload that uses the
+    /// URL.
     pub(crate) async fn get_upload_url(&self, bucket_id: String) -> Result<GetUploadUrlResponse> {
         const PATH: &str = "/b2api/v3/b2_get_upload_url";
-        let authorized = self.get_or_try_authorize().await?;
-        let url = format!(
-            "{}{}?bucketId={}",
-            authorized.storage_api_info.url, PATH, bucket_id
-        );
-        let req = self
-            .inner
-            .get(url)
-            .header(reqwest::header::AUTHORIZATION, authorized.token);
-        let res = req.send().await?;
+        let res = self
+            .send_authorized_once(&|authorized: &Authorized| {
+                let url = format!(
+                    "{}{}?bucketId={}",
+                    authorized.storage_api_info.url, PATH, bucket_id
+                );
+                self.request(reqwest::Method::GET, url)
+            })
+            .await?;
 
-        handle_b2_api_response(res).await
+        deserialize_b2_api_response(res).await
     }
 
-    pub(crate) async fn upload_file_from_reader<R: AsyncRead + Unpin>(
+    pub(crate) async fn upload_file(
         &self,
         upload_url: String,
         authorization_token: String,
-        mut reader: R,
         name: String,
+        data: Bytes,
+        config: &UploadConfig,
+        options: &UploadOptions,
     ) -> Result<UploadFileResponse> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf).await.unwrap();
+        let sum = sha1_hex(&data);
 
-        let mut hasher = Sha1::new();
-        hasher.update(&buf);
-        let sum = format!("{:x}", hasher.finalize());
+        let content_length = data.len();
 
-        let content_type = "b2/x-auto";
-        let content_length = buf.len();
-
-        let req = self
-            .inner
-            .post(upload_url)
+        let mut req = self
+            .request(reqwest::Method::POST, upload_url)
             .header(reqwest::header::AUTHORIZATION, authorization_token)
-            .header("X-Bz-File-Name", name)
-            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .header("X-Bz-File-Name", encode_file_name(&name))
             .header(reqwest::header::CONTENT_LENGTH, content_length)
             .header("X-Bz-Content-Sha1", sum)
-            .body(buf);
+            .body(data);
+        req = options.apply(req);
+        req = config.apply_upload_headers(req);
 
         let res = req.send().await?;
 
         handle_b2_api_response(res).await
     }
 
-    pub(crate) async fn upload_file<P: AsRef<Path>>(
+    pub(crate) async fn _start_large_file(
         &self,
-        upload_url: String,
-        authorization_token: String,
-        path: P,
-        name: String,
-    ) -> Result<UploadFileResponse> {
-        let file = tokio::fs::read(path).await.unwrap();
-        let mut hasher = Sha1::new();
-        hasher.update(&file);
-        let sum = format!("{:x}", hasher.finalize());
+        req: StartLargeFileRequest,
+    ) -> Result<LargeFileResponse> {
+        const PATH: &str = "/b2api/v3/b2_start_large_file";
 
-        let content_type = "b2/x-auto";
-        let content_length = file.len();
+        self.post_json_non_idempotent(PATH, &req).await
+    }
 
-        let req = self
-            .inner
-            .post(upload_url)
+    /// Sent only once, part uploads retry it along with the part that uses
+    /// the URL.
+    pub(crate) async fn _get_upload_part_url(
+        &self,
+        req: GetUploadPartUrlRequest,
+    ) -> Result<GetUploadPartUrlResponse> {
+        const PATH: &str = "/b2api/v3/b2_get_upload_part_url";
+
+        self.post_json_once(PATH, &req).await
+    }
+
+    pub(crate) async fn _upload_part(
+        &self,
+        upload_url: String,
+        authorization_token: String,
+        part_number: u16,
+        data: Bytes,
+        customer_key: Option<&CustomerKey>,
+    ) -> Result<UploadPartResponse> {
+        let sum = sha1_hex(&data);
+
+        let mut req = self
+            .request(reqwest::Method::POST, upload_url)
             .header(reqwest::header::AUTHORIZATION, authorization_token)
-            .header("X-Bz-File-Name", name)
-            .header(reqwest::header::CONTENT_TYPE, content_type)
-            .header(reqwest::header::CONTENT_LENGTH, content_length)
+            .header("X-Bz-Part-Number", part_number)
+            .header(reqwest::header::CONTENT_LENGTH, data.len())
             .header("X-Bz-Content-Sha1", sum)
-            .body(file);
+            .body(data);
+        if let Some(key) = customer_key {
+            req = key.apply(req);
+        }
 
         let res = req.send().await?;
 
         handle_b2_api_response(res).await
     }
 
+    pub(crate) async fn _finish_large_file(
+        &self,
+        req: FinishLargeFileRequest,
+    ) -> Result<UploadFileResponse> {
+        const PATH: &str = "/b2api/v3/b2_finish_large_file";
+
+        self.post_json(PATH, &req).await
+    }
+
+    pub(crate) async fn _cancel_large_file(&self, req: CancelLargeFileRequest) -> Result<()> {
+        const PATH: &str = "/b2api/v3/b2_cancel_large_file";
+
+        self.post_json::<_, IgnoredAny>(PATH, &req).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn _list_unfinished_large_files(
+        &self,
+        req: ListUnfinishedLargeFilesRequest,
+    ) -> Result<ListUnfinishedLargeFilesResponse> {
+        const PATH: &str = "/b2api/v3/b2_list_unfinished_large_files";
+
+        self.post_json(PATH, &req).await
+    }
+
+    pub(crate) async fn _list_parts(&self, req: ListPartsRequest) -> Result<ListPartsResponse> {
+        const PATH: &str = "/b2api/v3/b2_list_parts";
+
+        self.post_json(PATH, &req).await
+    }
+
+    pub(crate) async fn _delete_file_version(
+        &self,
+        req: DeleteFileVersionRequest,
+    ) -> Result<DeleteFileVersionResponse> {
+        const PATH: &str = "/b2api/v3/b2_delete_file_version";
+
+        self.post_json(PATH, &req).await
+    }
+
+    pub(crate) async fn _hide_file(&self, req: HideFileRequest) -> Result<UploadFileResponse> {
+        const PATH: &str = "/b2api/v3/b2_hide_file";
+
+        self.post_json_non_idempotent(PATH, &req).await
+    }
+
+    pub(crate) async fn _unhide_file(&self, req: UnhideFileRequest) -> Result<UploadFileResponse> {
+        const PATH: &str = "/b2api/v3/b2_unhide_file";
+
+        self.post_json(PATH, &req).await
+    }
+
+    pub(crate) async fn _get_download_authorization(
+        &self,
+        req: GetDownloadAuthorizationRequest,
+    ) -> Result<GetDownloadAuthorizationResponse> {
+        const PATH: &str = "/b2api/v3/b2_get_download_authorization";
+
+        self.post_json(PATH, &req).await
+    }
+
+    pub(crate) async fn _update_file_retention(
+        &self,
+        req: UpdateFileRetentionRequest,
+    ) -> Result<UpdateFileRetentionResponse> {
+        const PATH: &str = "/b2api/v3/b2_update_file_retention";
+
+        self.post_json(PATH, &req).await
+    }
+
+    pub(crate) async fn _update_file_legal_hold(
+        &self,
+        req: UpdateFileLegalHoldRequest,
+    ) -> Result<()> {
+        const PATH: &str = "/b2api/v3/b2_update_file_legal_hold";
+
+        self.post_json::<_, IgnoredAny>(PATH, &req).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn _copy_file(&self, req: CopyFileRequest) -> Result<UploadFileResponse> {
+        const PATH: &str = "/b2api/v3/b2_copy_file";
+
+        self.post_json_non_idempotent(PATH, &req).await
+    }
+
+    pub(crate) async fn _copy_part(&self, req: CopyPartRequest) -> Result<UploadPartResponse> {
+        const PATH: &str = "/b2api/v3/b2_copy_part";
+
+        self.post_json_non_idempotent(PATH, &req).await
+    }
+
+    async fn post_json<B, T>(&self, path: &str, body: &B) -> Result<T>
+    where
+        B: Serialize,
+        T: DeserializeOwned,
+    {
+        let res = self
+            .send_authorized(|authorized| {
+                let url = format!("{}{}", authorized.storage_api_info.url, path);
+                self.request(reqwest::Method::POST, url).json(body)
+            })
+            .await?;
+
+        deserialize_b2_api_response(res).await
+    }
+
+    /// Like [`Client::post_json`], for calls that must not take effect twice.
+    /// They are only retried after errors showing that B2 did not act on the
+    /// request, see [`Error::is_retryable_non_idempotent`].
+    async fn post_json_non_idempotent<B, T>(&self, path: &str, body: &B) -> Result<T>
+    where
+        B: Serialize,
+        T: DeserializeOwned,
+    {
+        let res = self
+            .send_authorized_when(
+                |authorized| {
+                    let url = format!("{}{}", authorized.storage_api_info.url, path);
+                    self.request(reqwest::Method::POST, url).json(body)
+                },
+                Error::is_retryable_non_idempotent,
+            )
+            .await?;
+
+        deserialize_b2_api_response(res).await
+    }
+
+    /// Like [`Client::post_json`], without retrying, for calls that are
+    /// retried as part of a bigger operation.
+    async fn post_json_once<B, T>(&self, path: &str, body: &B) -> Result<T>
+    where
+        B: Serialize,
+        T: DeserializeOwned,
+    {
+        let res = self
+            .send_authorized_once(&|authorized: &Authorized| {
+                let url = format!("{}{}", authorized.storage_api_info.url, path);
+                self.request(reqwest::Method::POST, url).json(body)
+            })
+            .await?;
+
+        deserialize_b2_api_response(res).await
+    }
+
     pub(crate) async fn _list_buckets(
         &self,
-        mut req: ListBucketsRequest,
+        req: ListBucketsRequest,
     ) -> Result<ListBucketsResponse> {
         const PATH: &str = "/b2api/v3/b2_list_buckets";
 
-        let authorized = self.get_or_try_authorize().await?;
+        let res = self
+            .send_authorized(|authorized| {
+                let mut req = req.clone();
+                req.account_id = authorized.id.clone();
 
-        req.account_id = authorized.id;
+                let url = format!("{}{}", authorized.storage_api_info.url, PATH);
+                self.request(reqwest::Method::POST, url).json(&req)
+            })
+            .await?;
 
-        let url = format!("{}{}", authorized.storage_api_info.url, PATH);
-        let req = self
-            .inner
-            .post(url)
-            .header(reqwest::header::AUTHORIZATION, authorized.token)
-            .json(&req);
+        deserialize_b2_api_response(res).await
+    }
 
-        let res = req.send().await?;
+    pub(crate) async fn _create_bucket(
+        &self,
+        mut req: CreateBucketRequest,
+    ) -> Result<ListBucketsBuckets> {
+        const PATH: &str = "/b2api/v3/b2_create_bucket";
+        req.account_id = self.account_id().await?;
 
-        handle_b2_api_response(res).await
+        self.post_json_non_idempotent(PATH, &req).await
+    }
+
+    pub(crate) async fn _update_bucket(
+        &self,
+        mut req: UpdateBucketRequest,
+    ) -> Result<ListBucketsBuckets> {
+        const PATH: &str = "/b2api/v3/b2_update_bucket";
+        req.account_id = self.account_id().await?;
+
+        self.post_json(PATH, &req).await
+    }
+
+    pub(crate) async fn _delete_bucket(&self, mut req: DeleteBucketRequest) -> Result<()> {
+        const PATH: &str = "/b2api/v3/b2_delete_bucket";
+        req.account_id = self.account_id().await?;
+
+        self.post_json::<_, IgnoredAny>(PATH, &req).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn _create_key(&self, mut req: CreateKeyRequest) -> Result<CreatedKey> {
+        const PATH: &str = "/b2api/v3/b2_create_key";
+        req.account_id = self.account_id().await?;
+
+        self.post_json_non_idempotent(PATH, &req).await
+    }
+
+    pub(crate) async fn _list_keys(&self, mut req: ListKeysRequest) -> Result<ListKeysResponse> {
+        const PATH: &str = "/b2api/v3/b2_list_keys";
+        req.account_id = self.account_id().await?;
+
+        self.post_json(PATH, &req).await
+    }
+
+    pub(crate) async fn _delete_key(&self, req: DeleteKeyRequest) -> Result<Key> {
+        const PATH: &str = "/b2api/v3/b2_delete_key";
+
+        self.post_json(PATH, &req).await
     }
 
     pub(crate) async fn _list_file_names(
@@ -191,24 +572,127 @@ impl Client {
     ) -> Result<ListFileNamesResponse> {
         const PATH: &str = "/b2api/v3/b2_list_file_names";
 
-        let authorized = self.get_or_try_authorize().await?;
+        let res = self
+            .send_authorized(|authorized| {
+                let url = format!("{}{}", authorized.storage_api_info.url, PATH);
+                self.request(reqwest::Method::GET, url).query(&req)
+            })
+            .await?;
 
-        let url = format!("{}{}", authorized.storage_api_info.url, PATH);
-        let req = self
-            .inner
-            .get(url)
-            .header(reqwest::header::AUTHORIZATION, authorized.token)
-            .query(&req);
+        deserialize_b2_api_response(res).await
+    }
 
-        let res = req.send().await?;
+    pub(crate) async fn _list_file_versions(
+        &self,
+        req: ListFileVersionsRequest,
+    ) -> Result<ListFileVersionsResponse> {
+        const PATH: &str = "/b2api/v3/b2_list_file_versions";
 
-        handle_b2_api_response(res).await
+        self.post_json(PATH, &req).await
+    }
+
+    pub(crate) async fn _download_file(
+        &self,
+        req: DownloadFileRequest,
+    ) -> Result<reqwest::Response> {
+        const PATH: &str = "/b2api/v3/b2_download_file_by_id";
+        if let DownloadTarget::Name {
+            bucket_name,
+            file_name,
+        } = &req.target
+        {
+            self.check_allowed(None, Some(bucket_name), Some(file_name))
+                .await?;
+        }
+
+        let method = if req.head {
+            reqwest::Method::HEAD
+        } else {
+            reqwest::Method::GET
+        };
+
+        self.send_authorized(|authorized| {
+            let download_url = &authorized.storage_api_info.download_url;
+            let mut builder = match &req.target {
+                DownloadTarget::Id(id) => self
+                    .request(method.clone(), format!("{}{}", download_url, PATH))
+                    .query(&[("fileId", id)]),
+                DownloadTarget::Name {
+                    bucket_name,
+                    file_name,
+                } => self.request(
+                    method.clone(),
+                    file_url(download_url, bucket_name, file_name),
+                ),
+            };
+            if let Some(range) = req.range {
+                builder = builder.header(reqwest::header::RANGE, range.header_value());
+            }
+            if let Some(key) = &req.customer_key {
+                builder = key.apply(builder);
+            }
+            builder
+        })
+        .await
+    }
+
+    pub fn download_file_by_id<T: AsRef<str>>(&self, id: T) -> DownloadFileBuilder {
+        DownloadFileBuilder::by_id(self.clone(), id)
+    }
+
+    pub async fn open_file_by_id<T: AsRef<str>>(&self, id: T) -> Result<RemoteFile> {
+        let target = DownloadTarget::Id(id.as_ref().to_string());
+
+        RemoteFile::open(self.clone(), target, None).await
+    }
+
+    /// Like [`Client::open_file_by_id`], for a file encrypted with
+    /// `customer_key` (SSE-C).
+    pub async fn open_file_by_id_with_key<T: AsRef<str>>(
+        &self,
+        id: T,
+        customer_key: CustomerKey,
+    ) -> Result<RemoteFile> {
+        let target = DownloadTarget::Id(id.as_ref().to_string());
+
+        RemoteFile::open(self.clone(), target, Some(customer_key)).await
     }
 
     pub async fn list_buckets(&self) -> ListBucketsBuilder {
         ListBucketsBuilder::new(self.clone())
     }
 
+    pub fn create_bucket<T: AsRef<str>>(
+        &self,
+        bucket_name: T,
+        bucket_type: BucketType,
+    ) -> CreateBucketBuilder {
+        CreateBucketBuilder::new(self.clone(), bucket_name, bucket_type)
+    }
+
+    /// Creates an application key with the given capabilities, which
+    /// requires the `writeKeys` capability.
+    pub fn create_key<T, I>(&self, key_name: T, capabilities: I) -> CreateKeyBuilder
+    where
+        T: AsRef<str>,
+        I: IntoIterator<Item = Capability>,
+    {
+        CreateKeyBuilder::new(self.clone(), key_name, capabilities)
+    }
+
+    pub fn list_keys(&self) -> ListKeysBuilder {
+        ListKeysBuilder::new(self.clone())
+    }
+
+    /// Deletes an application key, returning it as it was.
+    pub async fn delete_key<T: AsRef<str>>(&self, id: T) -> Result<Key> {
+        let req = DeleteKeyRequest {
+            application_key_id: id.as_ref().to_string(),
+        };
+
+        self._delete_key(req).await
+    }
+
     pub async fn bucket<T: AsRef<str>>(&self, bucket_name: T) -> Result<Option<Bucket>> {
         let buckets = ListBucketsBuilder::new(self.clone())
             .bucket_name(bucket_name.as_ref())
@@ -221,16 +705,69 @@ impl Client {
     }
 }
 
+pub(crate) fn sha1_hex(data: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(data);
+    format!("{:x}", hasher.finalize())
+}
+
+async fn check_b2_api_response(res: reqwest::Response) -> Result<reqwest::Response> {
+    check_response(res, ErrorKind::from).await
+}
+
+/// Like [`check_b2_api_response`], for responses to requests sent with the
+/// account authorization token. Errors of `HEAD` requests come without the
+/// error code that tells an expired token apart, so a bare 401 is taken as
+/// one to have the token renewed.
+async fn check_authorized_response(res: reqwest::Response) -> Result<reqwest::Response> {
+    check_response(res, |status| match status {
+        reqwest::StatusCode::UNAUTHORIZED => ErrorKind::ExpiredAuthToken,
+        status => ErrorKind::from(status),
+    })
+    .await
+}
+
+/// Turns error responses into errors, using `bare_status_kind` for those
+/// without an error body.
+async fn check_response<F>(res: reqwest::Response, bare_status_kind: F) -> Result<reqwest::Response>
+where
+    F: FnOnce(reqwest::StatusCode) -> ErrorKind,
+{
+    let status = res.status();
+    if status.is_client_error() || status.is_server_error() {
+        let retry_after = res
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_secs);
+
+        // Responses to `HEAD` requests come without an error body.
+        let body = res.bytes().await?;
+        let err: Error = match serde_json::from_slice::<ErrorResponse>(&body) {
+            Ok(err_response) => err_response.into(),
+            Err(_) => Error::new(bare_status_kind(status), status.to_string()),
+        };
+
+        return Err(err.with_retry_after(retry_after));
+    }
+
+    Ok(res)
+}
+
 async fn handle_b2_api_response<T>(res: reqwest::Response) -> Result<T>
 where
     T: DeserializeOwned,
 {
-    if res.status().is_client_error() || res.status().is_server_error() {
-        let err_response = res.json::<ErrorResponse>().await?;
+    let res = check_b2_api_response(res).await?;
 
-        return Err(err_response.into());
-    }
+    deserialize_b2_api_response(res).await
+}
 
+async fn deserialize_b2_api_response<T>(res: reqwest::Response) -> Result<T>
+where
+    T: DeserializeOwned,
+{
     match res.json::<T>().await {
         Ok(res) => Ok(res),
         Err(err) => {
@@ -239,3 +776,45 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restricted_authorize_account_response_deserializes() {
+        let res: AuthorizeAccountResponse = serde_json::from_value(serde_json::json!({
+            "accountId": "a30f20426f0b",
+            "apiInfo": {
+                "groupsApi": {},
+                "storageApi": {
+                    "absoluteMinimumPartSize": 5000000,
+                    "apiUrl": "https://api001.backblazeb2.com",
+                    "bucketId": "4a48fe8875c6214145260818",
+                    "bucketName": "photos",
+                    "capabilities": ["listFiles", "readFiles"],
+                    "downloadUrl": "https://f001.backblazeb2.com",
+                    "infoType": "storageApi",
+                    "namePrefix": "public/",
+                    "recommendedPartSize": 100000000,
+                    "s3ApiUrl": "https://s3.us-west-001.backblazeb2.com",
+                },
+            },
+            "applicationKeyExpirationTimestamp": null,
+            "authorizationToken": "4_0022623512fc8f80000000001_0186e431_d18d02_acct_tH7VW03boebOXayIc43-sxptpfA=",
+        }))
+        .unwrap();
+
+        let allowed = res.api_info.storage_api.allowed;
+        assert_eq!(
+            allowed.bucket_id.as_deref(),
+            Some("4a48fe8875c6214145260818")
+        );
+        assert_eq!(allowed.bucket_name.as_deref(), Some("photos"));
+        assert_eq!(allowed.name_prefix.as_deref(), Some("public/"));
+        assert_eq!(
+            allowed.capabilities,
+            [Capability::ListFiles, Capability::ReadFiles]
+        );
+    }
+}
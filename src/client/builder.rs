@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use crate::error::ErrorKind;
+use crate::{Account, Client, Error, Result, RetryPolicy};
+
+pub(super) const DEFAULT_API_URL: &str = "https://api.backblazeb2.com";
+pub(super) const DEFAULT_USER_AGENT: &str = concat!("rustblaze/", env!("CARGO_PKG_VERSION"));
+
+/// Configures a [`Client`].
+///
+/// Connect and read timeouts and the proxy are settings of the underlying
+/// HTTP client, so they cannot be combined with [`ClientBuilder::http_client`].
+/// The user agent and total timeout are set on every request and work with
+/// either.
+#[derive(Debug)]
+pub struct ClientBuilder {
+    id: String,
+    secret: String,
+    api_url: String,
+    user_agent_suffix: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    proxy: Option<reqwest::Proxy>,
+    http_client: Option<reqwest::Client>,
+    retry_policy: RetryPolicy,
+}
+
+impl ClientBuilder {
+    pub(crate) fn new(id: String, secret: String) -> Self {
+        Self {
+            id,
+            secret,
+            api_url: DEFAULT_API_URL.to_string(),
+            user_agent_suffix: Default::default(),
+            timeout: Default::default(),
+            connect_timeout: Default::default(),
+            read_timeout: Default::default(),
+            proxy: Default::default(),
+            http_client: Default::default(),
+            retry_policy: Default::default(),
+        }
+    }
+
+    /// URL the account is authorized against, defaults to
+    /// `https://api.backblazeb2.com`. All other URLs are taken from the
+    /// authorization response, so this is enough to point the client at
+    /// another realm or a local emulator.
+    pub fn api_url<T: AsRef<str>>(&mut self, api_url: T) -> &mut Self {
+        self.api_url = api_url.as_ref().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Appended to the `rustblaze/<version>` user agent, B2 asks for it to
+    /// identify the application, e.g. `my-app/1.2.0`.
+    pub fn user_agent_suffix<T: AsRef<str>>(&mut self, suffix: T) -> &mut Self {
+        self.user_agent_suffix = Some(suffix.as_ref().to_string());
+        self
+    }
+
+    /// Limit for a whole request, including reading the response body. Keep
+    /// in mind that this also applies to downloads of big files.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Limit for each read from a connection, rather than for the whole
+    /// request.
+    pub fn read_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    pub fn proxy(&mut self, proxy: reqwest::Proxy) -> &mut Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Sends all requests with `client`, e.g. to share its connection pool.
+    pub fn http_client(&mut self, client: reqwest::Client) -> &mut Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    pub fn retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn build(&mut self) -> Result<Client> {
+        let inner = match self.http_client.clone() {
+            Some(client) => {
+                if self.connect_timeout.is_some()
+                    || self.read_timeout.is_some()
+                    || self.proxy.is_some()
+                {
+                    return Err(Error::new(
+                        ErrorKind::Builder,
+                        "connect timeout, read timeout and proxy cannot be applied to a provided HTTP client",
+                    ));
+                }
+                client
+            }
+            None => {
+                let mut builder = reqwest::Client::builder();
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = self.read_timeout {
+                    builder = builder.read_timeout(timeout);
+                }
+                if let Some(proxy) = self.proxy.clone() {
+                    builder = builder.proxy(proxy);
+                }
+                builder.build()?
+            }
+        };
+
+        let user_agent = match &self.user_agent_suffix {
+            Some(suffix) => format!("{} {}", DEFAULT_USER_AGENT, suffix),
+            None => DEFAULT_USER_AGENT.to_string(),
+        };
+
+        Ok(Client {
+            inner,
+            account: Account::new(self.id.clone(), self.secret.clone()),
+            retry_policy: self.retry_policy.clone(),
+            api_url: self.api_url.clone(),
+            user_agent,
+            timeout: self.timeout,
+        })
+    }
+}
@@ -1,4 +1,5 @@
 use ::std::result::Result as StdResult;
+use ::std::time::Duration;
 use serde::Deserialize;
 
 pub type Result<T> = ::std::result::Result<T, Error>;
@@ -7,9 +8,23 @@ pub type Result<T> = ::std::result::Result<T, Error>;
 pub struct Error {
     kind: ErrorKind,
     message: String,
+    retry_after: Option<Duration>,
 }
 
 impl Error {
+    pub(crate) fn new<T: Into<String>>(kind: ErrorKind, message: T) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            retry_after: None,
+        }
+    }
+
+    pub(crate) fn with_retry_after(mut self, retry_after: Option<Duration>) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
     pub fn kind(&self) -> ErrorKind {
         self.kind
     }
@@ -17,6 +32,50 @@ impl Error {
     pub fn message(&self) -> &str {
         &self.message
     }
+
+    /// Whether the request may succeed when sent again, e.g. because B2 was
+    /// temporarily busy or the connection dropped.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind,
+            ErrorKind::RequestTimeout
+                | ErrorKind::TooManyRequests
+                | ErrorKind::InternalError
+                | ErrorKind::ServiceUnavailable
+                | ErrorKind::Connect
+                | ErrorKind::Timeout
+                | ErrorKind::Request
+        )
+    }
+
+    /// Whether a call that must not take effect twice, like creating a key,
+    /// may be sent again. Timeouts and connections failing in transit are
+    /// excluded, as B2 may have acted on the request before they happened.
+    pub(crate) fn is_retryable_non_idempotent(&self) -> bool {
+        self.is_retryable() && !matches!(self.kind, ErrorKind::Timeout | ErrorKind::Request)
+    }
+
+    /// Whether an upload failing with this error should be tried again with
+    /// a new upload URL, which is also the case when the token of the upload
+    /// URL expired.
+    pub(crate) fn is_retryable_upload(&self) -> bool {
+        self.is_retryable() || self.is_rejected_auth_token()
+    }
+
+    /// How long B2 asked to wait before trying again, from the `Retry-After`
+    /// header.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+
+    /// Whether the authorization token was expired or invalid, which is
+    /// fixed by authorizing again.
+    pub(crate) fn is_rejected_auth_token(&self) -> bool {
+        matches!(
+            self.kind,
+            ErrorKind::ExpiredAuthToken | ErrorKind::BadAuthToken
+        )
+    }
 }
 
 impl ::std::fmt::Display for Error {
@@ -30,15 +89,16 @@ impl ::std::error::Error for Error {}
 impl From<ErrorResponse> for Error {
     fn from(res: ErrorResponse) -> Self {
         let message = res.message.clone();
+        let status = reqwest::StatusCode::from_u16(res.status).ok();
         let kind = match ErrorKind::try_from(res) {
             Ok(k) => k,
             Err(e) => {
                 tracing::warn!(message = "encountered unknown error code", code = e.0);
-                ErrorKind::Unknown
+                status.map_or(ErrorKind::Unknown, ErrorKind::from)
             }
         };
 
-        Self { kind, message }
+        Self::new(kind, message)
     }
 }
 
@@ -49,11 +109,24 @@ impl From<reqwest::Error> for Error {
             ErrorKind::Connect => "could not connect",
             ErrorKind::Timeout => "timed out",
             ErrorKind::Deserialize => "invalid or malformed response",
+            ErrorKind::Request => "request or response failed in transit",
+            ErrorKind::Builder => "could not build HTTP client",
             _ => "unknown error related to communication",
-        }
-        .to_string();
+        };
+
+        Self::new(kind, message)
+    }
+}
+
+impl From<::std::io::Error> for Error {
+    fn from(err: ::std::io::Error) -> Self {
+        Self::new(ErrorKind::Io, err.to_string())
+    }
+}
 
-        Self { kind, message }
+impl From<reqwest::StatusCode> for Error {
+    fn from(status: reqwest::StatusCode) -> Self {
+        Self::new(ErrorKind::from(status), status.to_string())
     }
 }
 
@@ -64,12 +137,22 @@ pub enum ErrorKind {
     ExpiredAuthToken,
     BadBucketId,
     BadRequest,
+    RequestTimeout,
+    NotFound,
+    Conflict,
+    RangeNotSatisfiable,
     Unauthorized,
     Unsupported,
     TransactionCapExceeded,
+    TooManyRequests,
+    InternalError,
+    ServiceUnavailable,
     Connect,
     Timeout,
+    Request,
     Deserialize,
+    Builder,
+    Io,
     Unknown,
 }
 
@@ -93,9 +176,16 @@ impl TryFrom<ErrorResponse> for ErrorKind {
             "expired_auth_token" => Ok(Self::ExpiredAuthToken),
             "bad_bucket_id" => Ok(Self::BadBucketId),
             "bad_request" => Ok(Self::BadRequest),
+            "request_timeout" => Ok(Self::RequestTimeout),
+            "not_found" => Ok(Self::NotFound),
+            "conflict" => Ok(Self::Conflict),
+            "range_not_satisfiable" => Ok(Self::RangeNotSatisfiable),
             "unauthorized" => Ok(Self::Unauthorized),
             "unsupported" => Ok(Self::Unsupported),
             "transaction_cap_exceeded" => Ok(Self::TransactionCapExceeded),
+            "too_many_requests" => Ok(Self::TooManyRequests),
+            "internal_error" => Ok(Self::InternalError),
+            "service_unavailable" => Ok(Self::ServiceUnavailable),
             code => Err(UnknownErrorCode(code.to_string())),
         }
     }
@@ -103,6 +193,9 @@ impl TryFrom<ErrorResponse> for ErrorKind {
 
 impl From<reqwest::Error> for ErrorKind {
     fn from(err: reqwest::Error) -> Self {
+        if err.is_builder() {
+            return Self::Builder;
+        }
         if err.is_connect() {
             return Self::Connect;
         }
@@ -112,8 +205,28 @@ impl From<reqwest::Error> for ErrorKind {
         if err.is_decode() {
             return Self::Deserialize;
         }
+        if err.is_request() || err.is_body() {
+            return Self::Request;
+        }
 
-        return Self::Unknown;
+        Self::Unknown
+    }
+}
+
+impl From<reqwest::StatusCode> for ErrorKind {
+    fn from(status: reqwest::StatusCode) -> Self {
+        match status.as_u16() {
+            400 => Self::BadRequest,
+            401 => Self::Unauthorized,
+            404 => Self::NotFound,
+            408 => Self::RequestTimeout,
+            409 => Self::Conflict,
+            416 => Self::RangeNotSatisfiable,
+            429 => Self::TooManyRequests,
+            500 => Self::InternalError,
+            503 => Self::ServiceUnavailable,
+            _ => Self::Unknown,
+        }
     }
 }
 
@@ -1,26 +1,165 @@
+mod delete;
+mod download;
+mod encryption;
+mod large;
 mod list;
+mod lock;
+mod remote;
 
-pub use list::{ListFileNamesBuilder, NextFileName};
+pub use delete::{DeleteFileVersionBuilder, DeletedFileVersion};
+pub use download::{Download, DownloadFileBuilder};
+pub use encryption::{CustomerKey, Encryption};
+pub use large::{
+    LargeFile, ListPartsBuilder, ListUnfinishedLargeFilesBuilder, Part, UploadPartUrl,
+};
+pub use list::{ListFileNamesBuilder, ListFileVersionsBuilder, NextFileName, NextFileVersion};
+pub use lock::{FileRetention, LegalHold, UpdateFileRetentionBuilder};
+pub use remote::RemoteFile;
 
+pub(crate) use delete::*;
+pub(crate) use download::*;
+pub(crate) use encryption::*;
+pub(crate) use large::*;
 pub(crate) use list::*;
+pub(crate) use lock::*;
 
-use crate::bucket::UploadFileResponse;
+use std::collections::HashMap;
 
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Deserialize;
+
+use crate::bucket::{CopyFileBuilder, Protected, ServerSideEncryption, UploadFileResponse};
+use crate::{Bucket, Result};
+
+/// Characters left as-is when a file name is put into a URL path, B2 treats
+/// `/` as an ordinary character of the name.
+const FILE_NAME_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'/');
+
+pub(crate) fn encode_file_name(name: &str) -> String {
+    utf8_percent_encode(name, FILE_NAME_ENCODE_SET).to_string()
+}
+
+/// URL that downloads the latest version of `file_name` from a bucket.
+pub(crate) fn file_url(download_url: &str, bucket_name: &str, file_name: &str) -> String {
+    format!(
+        "{}/file/{}/{}",
+        download_url,
+        bucket_name,
+        encode_file_name(file_name)
+    )
+}
+
+/// What a file version stands for.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileAction {
+    /// A large file that has been started but not finished or cancelled.
+    Start,
+    /// A file that was uploaded.
+    #[default]
+    Upload,
+    /// A hide marker, which hides all earlier versions of the file.
+    Hide,
+    /// A virtual folder, only listed when a delimiter is given.
+    Folder,
+}
+
+#[non_exhaustive]
 #[derive(Clone, Debug)]
 pub struct File {
+    /// Empty for [`FileAction::Folder`], which is not a file version.
     pub id: String,
     pub name: String,
+    pub account_id: String,
+    pub bucket_id: String,
     pub size: usize,
+    /// `none` for large files uploaded without a checksum of the whole file,
+    /// `None` for folders.
+    pub content_sha1: Option<String>,
+    pub content_md5: Option<String>,
     pub upload_timestamp: i64,
+    pub action: FileAction,
+    /// `None` for responses that do not include it, such as hiding a file.
+    pub content_type: Option<String>,
+    /// Custom information stored with the file, including headers like
+    /// `b2-content-disposition` that B2 sends when it is downloaded.
+    pub file_info: HashMap<String, String>,
+    /// How the file is encrypted at rest, `None` if it is not.
+    pub server_side_encryption: Option<ServerSideEncryption>,
+    /// Object Lock retention, `None` if there is none or the key may not
+    /// read it.
+    pub file_retention: Option<FileRetention>,
+    /// Object Lock legal hold, `None` if it was never set or the key may not
+    /// read it.
+    pub legal_hold: Option<LegalHold>,
 }
 
 impl From<UploadFileResponse> for File {
     fn from(res: UploadFileResponse) -> Self {
         Self {
-            id: res.file_id,
+            id: res.file_id.unwrap_or_default(),
             name: res.file_name,
+            account_id: res.account_id,
+            bucket_id: res.bucket_id,
             size: res.content_length,
+            content_sha1: res.content_sha1,
+            content_md5: res.content_md5,
             upload_timestamp: res.upload_timestamp,
+            action: res.action,
+            content_type: res.content_type,
+            file_info: res.file_info,
+            server_side_encryption: res.server_side_encryption.filter(|sse| sse.mode.is_some()),
+            file_retention: res
+                .file_retention
+                .and_then(Protected::into_value)
+                .filter(|retention| retention.mode.is_some()),
+            legal_hold: res.legal_hold.and_then(Protected::into_value),
         }
     }
 }
+
+impl File {
+    /// Copies this version of the file to `name` in `bucket`.
+    pub fn copy_to<T: AsRef<str>>(&self, bucket: &Bucket, name: T) -> CopyFileBuilder {
+        let mut builder = bucket.copy_file(&self.id, name);
+        builder.source_size(self.size as u64);
+        builder
+    }
+
+    /// Deletes this version of the file from `bucket`.
+    pub fn delete(&self, bucket: &Bucket) -> DeleteFileVersionBuilder {
+        bucket.delete_file_version(&self.name, &self.id)
+    }
+
+    /// Sets the retention of this version of the file in `bucket`.
+    pub fn update_retention(
+        &self,
+        bucket: &Bucket,
+        file_retention: FileRetention,
+    ) -> UpdateFileRetentionBuilder {
+        bucket.update_file_retention(&self.name, &self.id, file_retention)
+    }
+
+    /// Sets the legal hold of this version of the file in `bucket`.
+    pub async fn update_legal_hold(&self, bucket: &Bucket, legal_hold: LegalHold) -> Result<()> {
+        bucket
+            .update_file_legal_hold(&self.name, &self.id, legal_hold)
+            .await
+    }
+
+    /// Hides the file in `bucket`, see [`Bucket::hide_file`].
+    pub async fn hide(&self, bucket: &Bucket) -> Result<File> {
+        bucket.hide_file(&self.name).await
+    }
+
+    /// Makes the file visible again in `bucket`, see [`Bucket::unhide_file`].
+    pub async fn unhide(&self, bucket: &Bucket) -> Result<File> {
+        bucket.unhide_file(&self.name).await
+    }
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Client, Result};
+
+/// The file version removed by [`DeleteFileVersionBuilder::send`].
+#[derive(Clone, Debug)]
+pub struct DeletedFileVersion {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeleteFileVersionRequest {
+    file_name: String,
+    file_id: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    bypass_governance: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeleteFileVersionResponse {
+    file_id: String,
+    file_name: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct DeleteFileVersionBuilder {
+    inner: Client,
+    file_name: String,
+    file_id: String,
+    bypass_governance: bool,
+}
+
+impl DeleteFileVersionBuilder {
+    pub(crate) fn new<T, U>(client: Client, file_name: T, file_id: U) -> Self
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+    {
+        Self {
+            inner: client,
+            file_name: file_name.as_ref().to_string(),
+            file_id: file_id.as_ref().to_string(),
+            bypass_governance: Default::default(),
+        }
+    }
+
+    /// Deletes the version even if it is under governance mode retention,
+    /// which requires the `bypassGovernance` capability.
+    pub fn bypass_governance(&mut self, bypass_governance: bool) -> &mut Self {
+        self.bypass_governance = bypass_governance;
+        self
+    }
+
+    pub async fn send(&mut self) -> Result<DeletedFileVersion> {
+        self.inner
+            .check_allowed(None, None, Some(&self.file_name))
+            .await?;
+
+        let req = DeleteFileVersionRequest {
+            file_name: self.file_name.clone(),
+            file_id: self.file_id.clone(),
+            bypass_governance: self.bypass_governance,
+        };
+
+        let res = self.inner._delete_file_version(req).await?;
+
+        Ok(DeletedFileVersion {
+            id: res.file_id,
+            name: res.file_name,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HideFileRequest {
+    pub bucket_id: String,
+    pub file_name: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UnhideFileRequest {
+    pub bucket_id: String,
+    pub file_name: String,
+}
@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+use std::ops::{Bound, RangeBounds};
+
+use bytes::Bytes;
+use futures_util::{Stream, TryStreamExt};
+use percent_encoding::percent_decode_str;
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE};
+use tokio::io::AsyncRead;
+use tokio_util::io::StreamReader;
+
+use super::CustomerKey;
+use crate::bucket::{EncryptionMode, ServerSideEncryption};
+use crate::error::ErrorKind;
+use crate::{Client, Error, Result};
+
+const INFO_HEADER_PREFIX: &str = "x-bz-info-";
+
+#[derive(Clone, Debug)]
+pub(crate) enum DownloadTarget {
+    Id(String),
+    Name {
+        bucket_name: String,
+        file_name: String,
+    },
+}
+
+/// Inclusive byte range, an open `end` reads until the end of the file.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ByteRange {
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+impl ByteRange {
+    /// Fails with [`ErrorKind::BadRequest`] if `range` is empty, as B2 cannot
+    /// be asked for zero bytes.
+    pub(crate) fn from_bounds<R: RangeBounds<u64>>(range: R) -> Result<Self> {
+        let start = match range.start_bound() {
+            Bound::Included(&s) => Some(s),
+            Bound::Excluded(&s) => s.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&e) => Some(Some(e)),
+            Bound::Excluded(&e) => e.checked_sub(1).map(Some),
+            Bound::Unbounded => Some(None),
+        };
+
+        match (start, end) {
+            (Some(start), Some(end)) if end.is_none_or(|end| start <= end) => {
+                Ok(Self { start, end })
+            }
+            _ => Err(Error::new(
+                ErrorKind::BadRequest,
+                format!(
+                    "byte range {:?} is empty",
+                    (range.start_bound(), range.end_bound())
+                ),
+            )),
+        }
+    }
+
+    pub(crate) fn header_value(&self) -> String {
+        match self.end {
+            Some(end) => format!("bytes={}-{}", self.start, end),
+            None => format!("bytes={}-", self.start),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DownloadFileRequest {
+    pub target: DownloadTarget,
+    pub range: Option<ByteRange>,
+    /// Only fetch the headers with a `HEAD` request.
+    pub head: bool,
+    /// Key the file was encrypted with, required for SSE-C files.
+    pub customer_key: Option<CustomerKey>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DownloadFileBuilder {
+    inner: Client,
+    target: DownloadTarget,
+    range: Option<(Bound<u64>, Bound<u64>)>,
+    customer_key: Option<CustomerKey>,
+}
+
+impl DownloadFileBuilder {
+    pub(crate) fn by_id<T: AsRef<str>>(client: Client, file_id: T) -> Self {
+        Self {
+            inner: client,
+            target: DownloadTarget::Id(file_id.as_ref().to_string()),
+            range: Default::default(),
+            customer_key: Default::default(),
+        }
+    }
+
+    pub(crate) fn by_name<T, U>(client: Client, bucket_name: T, file_name: U) -> Self
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+    {
+        Self {
+            inner: client,
+            target: DownloadTarget::Name {
+                bucket_name: bucket_name.as_ref().to_string(),
+                file_name: file_name.as_ref().to_string(),
+            },
+            range: Default::default(),
+            customer_key: Default::default(),
+        }
+    }
+
+    /// Only download the given byte range of the file, which must not be
+    /// empty.
+    pub fn range<R: RangeBounds<u64>>(&mut self, range: R) -> &mut Self {
+        self.range = Some((range.start_bound().cloned(), range.end_bound().cloned()));
+        self
+    }
+
+    /// Key the file was encrypted with, required to download SSE-C files.
+    pub fn customer_key(&mut self, customer_key: CustomerKey) -> &mut Self {
+        self.customer_key = Some(customer_key);
+        self
+    }
+
+    pub async fn send(&mut self) -> Result<Download> {
+        let req = DownloadFileRequest {
+            target: self.target.clone(),
+            range: self.range.map(ByteRange::from_bounds).transpose()?,
+            head: false,
+            customer_key: self.customer_key.clone(),
+        };
+
+        let res = self.inner._download_file(req).await?;
+
+        Ok(Download::from_response(res))
+    }
+}
+
+/// An in-progress download.
+///
+/// The response headers are available as soon as this is returned, the body
+/// is only pulled from the network as it is consumed through
+/// [`Download::into_stream`] or [`Download::into_reader`].
+#[derive(Debug)]
+pub struct Download {
+    id: String,
+    name: String,
+    content_length: u64,
+    file_size: u64,
+    content_type: String,
+    content_sha1: Option<String>,
+    info: HashMap<String, String>,
+    upload_timestamp: i64,
+    server_side_encryption: Option<ServerSideEncryption>,
+    res: reqwest::Response,
+}
+
+impl Download {
+    pub(crate) fn from_response(res: reqwest::Response) -> Self {
+        let headers = res.headers();
+
+        // `HEAD` responses have no body, so the length has to come from the
+        // header rather than from `reqwest::Response::content_length`.
+        let content_length = header_str(headers, CONTENT_LENGTH.as_str())
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default();
+        // Partial responses carry the full size as `bytes <start>-<end>/<size>`.
+        let file_size = header_str(headers, CONTENT_RANGE.as_str())
+            .and_then(|s| s.rsplit_once('/'))
+            .and_then(|(_, size)| size.parse().ok())
+            .unwrap_or(content_length);
+
+        let info = headers
+            .iter()
+            .filter_map(|(k, v)| {
+                let key = k.as_str().strip_prefix(INFO_HEADER_PREFIX)?;
+                let value = v.to_str().ok()?;
+                Some((decode(key), decode(value)))
+            })
+            .collect();
+
+        let server_side_encryption = match (
+            header_str(headers, "X-Bz-Server-Side-Encryption"),
+            header_str(headers, "X-Bz-Server-Side-Encryption-Customer-Algorithm"),
+        ) {
+            (Some(algorithm), _) => Some((EncryptionMode::SseB2, algorithm)),
+            (None, Some(algorithm)) => Some((EncryptionMode::SseC, algorithm)),
+            (None, None) => None,
+        }
+        .map(|(mode, algorithm)| ServerSideEncryption {
+            mode: Some(mode),
+            algorithm: Some(algorithm.to_string()),
+        });
+
+        Self {
+            id: header_str(headers, "X-Bz-File-Id")
+                .unwrap_or_default()
+                .to_string(),
+            name: header_str(headers, "X-Bz-File-Name")
+                .map(decode)
+                .unwrap_or_default(),
+            content_length,
+            file_size,
+            content_type: header_str(headers, CONTENT_TYPE.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            content_sha1: header_str(headers, "X-Bz-Content-Sha1")
+                .filter(|s| *s != "none")
+                .map(ToString::to_string),
+            info,
+            upload_timestamp: header_str(headers, "X-Bz-Upload-Timestamp")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default(),
+            server_side_encryption,
+            res,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Number of bytes in the response body.
+    pub fn content_length(&self) -> u64 {
+        self.content_length
+    }
+
+    /// Size of the whole file, which differs from
+    /// [`Download::content_length`] when only a range was requested.
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    pub fn content_type(&self) -> &str {
+        self.content_type.as_str()
+    }
+
+    /// SHA1 checksum of the file, `None` for large files uploaded without one.
+    pub fn content_sha1(&self) -> Option<&str> {
+        self.content_sha1.as_deref()
+    }
+
+    /// Custom file information sent as `X-Bz-Info-*` headers.
+    pub fn info(&self) -> &HashMap<String, String> {
+        &self.info
+    }
+
+    pub(crate) fn headers(&self) -> &HeaderMap {
+        self.res.headers()
+    }
+
+    pub fn upload_timestamp(&self) -> i64 {
+        self.upload_timestamp
+    }
+
+    /// How the file is encrypted at rest, `None` if it is not.
+    pub fn server_side_encryption(&self) -> Option<&ServerSideEncryption> {
+        self.server_side_encryption.as_ref()
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = Result<Bytes>> {
+        self.res.bytes_stream().map_err(Error::from)
+    }
+
+    pub fn into_reader(self) -> impl AsyncRead {
+        StreamReader::new(self.res.bytes_stream().map_err(::std::io::Error::other))
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::fails_with;
+
+    fn bounds<R: RangeBounds<u64>>(range: R) -> (u64, Option<u64>) {
+        let range = ByteRange::from_bounds(range).unwrap();
+        (range.start, range.end)
+    }
+
+    #[test]
+    fn byte_range_from_bounds() {
+        assert_eq!(bounds(10..20), (10, Some(19)));
+        assert_eq!(bounds(10..=20), (10, Some(20)));
+        assert_eq!(bounds(5..), (5, None));
+        assert_eq!(bounds(..100), (0, Some(99)));
+        assert_eq!(bounds(..), (0, None));
+        assert_eq!(
+            bounds((Bound::Excluded(4), Bound::Included(8))),
+            (5, Some(8))
+        );
+    }
+
+    #[test]
+    fn byte_range_edges() {
+        assert_eq!(bounds(..1), (0, Some(0)));
+        assert_eq!(bounds(5..6), (5, Some(5)));
+        assert_eq!(bounds(5..=5), (5, Some(5)));
+        assert_eq!(bounds(u64::MAX..), (u64::MAX, None));
+        assert_eq!(bounds(..=u64::MAX), (0, Some(u64::MAX)));
+        assert_eq!(
+            bounds((Bound::Excluded(u64::MAX - 1), Bound::Unbounded)),
+            (u64::MAX, None)
+        );
+    }
+
+    #[test]
+    fn empty_byte_ranges_are_rejected() {
+        assert!(fails_with(
+            ByteRange::from_bounds(..0),
+            ErrorKind::BadRequest
+        ));
+        assert!(fails_with(
+            ByteRange::from_bounds(5..5),
+            ErrorKind::BadRequest
+        ));
+        // Reversed ranges, written as bounds as the range syntax is linted.
+        assert!(fails_with(
+            ByteRange::from_bounds((Bound::Included(6), Bound::Excluded(5))),
+            ErrorKind::BadRequest
+        ));
+        assert!(fails_with(
+            ByteRange::from_bounds((Bound::Included(6), Bound::Included(5))),
+            ErrorKind::BadRequest
+        ));
+        assert!(fails_with(
+            ByteRange::from_bounds((Bound::Excluded(u64::MAX), Bound::Unbounded)),
+            ErrorKind::BadRequest
+        ));
+        assert!(fails_with(
+            ByteRange::from_bounds((Bound::Excluded(4), Bound::Excluded(5))),
+            ErrorKind::BadRequest
+        ));
+    }
+
+    #[test]
+    fn byte_range_header_value() {
+        let header_value = |range: ByteRange| range.header_value();
+
+        assert_eq!(
+            header_value(ByteRange::from_bounds(0..=0).unwrap()),
+            "bytes=0-0"
+        );
+        assert_eq!(
+            header_value(ByteRange::from_bounds(..1).unwrap()),
+            "bytes=0-0"
+        );
+        assert_eq!(
+            header_value(ByteRange::from_bounds(10..20).unwrap()),
+            "bytes=10-19"
+        );
+        assert_eq!(
+            header_value(ByteRange::from_bounds(1024..).unwrap()),
+            "bytes=1024-"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use md5::{Digest, Md5};
+use reqwest::RequestBuilder;
+use serde::Serialize;
+
+use crate::bucket::EncryptionMode;
+
+const ALGORITHM: &str = "AES256";
+
+/// Server-side encryption requested for a file that is uploaded or copied.
+///
+/// Files uploaded without one are encrypted according to the default of the
+/// bucket.
+#[derive(Clone, Debug)]
+pub enum Encryption {
+    /// Encrypted with keys managed by B2.
+    SseB2,
+    /// Encrypted with a key of the client, which B2 does not store and which
+    /// has to be provided again to download or copy the file.
+    SseC(CustomerKey),
+}
+
+impl Encryption {
+    pub(crate) fn customer_key(&self) -> Option<&CustomerKey> {
+        match self {
+            Self::SseB2 => None,
+            Self::SseC(key) => Some(key),
+        }
+    }
+
+    /// Adds the headers that request this encryption on an upload.
+    pub(crate) fn apply(&self, req: RequestBuilder) -> RequestBuilder {
+        match self {
+            Self::SseB2 => req.header("X-Bz-Server-Side-Encryption", ALGORITHM),
+            Self::SseC(key) => key.apply(req),
+        }
+    }
+
+    pub(crate) fn to_request(&self) -> EncryptionRequest {
+        match self {
+            Self::SseB2 => EncryptionRequest {
+                mode: EncryptionMode::SseB2,
+                algorithm: ALGORITHM,
+                customer_key: None,
+                customer_key_md5: None,
+            },
+            Self::SseC(key) => key.to_request(),
+        }
+    }
+}
+
+/// A 256-bit AES key for SSE-C. It is never printed by `Debug`.
+#[derive(Clone)]
+pub struct CustomerKey([u8; 32]);
+
+impl CustomerKey {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    /// Adds the headers that pass this key along with a request.
+    pub(crate) fn apply(&self, req: RequestBuilder) -> RequestBuilder {
+        req.header("X-Bz-Server-Side-Encryption-Customer-Algorithm", ALGORITHM)
+            .header("X-Bz-Server-Side-Encryption-Customer-Key", self.encoded())
+            .header(
+                "X-Bz-Server-Side-Encryption-Customer-Key-Md5",
+                self.encoded_md5(),
+            )
+    }
+
+    pub(crate) fn to_request(&self) -> EncryptionRequest {
+        EncryptionRequest {
+            mode: EncryptionMode::SseC,
+            algorithm: ALGORITHM,
+            customer_key: Some(self.encoded()),
+            customer_key_md5: Some(self.encoded_md5()),
+        }
+    }
+
+    fn encoded(&self) -> String {
+        STANDARD.encode(self.0)
+    }
+
+    fn encoded_md5(&self) -> String {
+        STANDARD.encode(Md5::digest(self.0))
+    }
+}
+
+impl ::std::fmt::Debug for CustomerKey {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        f.debug_tuple("CustomerKey").field(&"..").finish()
+    }
+}
+
+/// Encryption as it is given in the body of JSON calls.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EncryptionRequest {
+    mode: EncryptionMode,
+    algorithm: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    customer_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    customer_key_md5: Option<String>,
+}
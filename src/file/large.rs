@@ -0,0 +1,309 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use super::{EncryptionRequest, FileRetention, LegalHold};
+use crate::{Client, Result};
+
+/// A large file that has been started but not yet finished or cancelled.
+///
+/// Parts can be uploaded concurrently from clones of the same handle, the
+/// SHA1 of every uploaded part is recorded so that the file can be finished
+/// without the caller keeping track of them.
+#[derive(Clone, Debug)]
+pub struct LargeFile {
+    id: String,
+    name: String,
+    content_type: String,
+    info: HashMap<String, String>,
+    upload_timestamp: i64,
+    parts: Arc<Mutex<BTreeMap<u16, Part>>>,
+}
+
+impl LargeFile {
+    pub(crate) fn from_response(res: LargeFileResponse) -> Self {
+        Self {
+            id: res.file_id,
+            name: res.file_name,
+            content_type: res.content_type,
+            info: res.file_info,
+            upload_timestamp: res.upload_timestamp,
+            parts: Default::default(),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Content type given when the file was started.
+    pub fn content_type(&self) -> &str {
+        self.content_type.as_str()
+    }
+
+    /// Custom file information given when the file was started.
+    pub fn info(&self) -> &HashMap<String, String> {
+        &self.info
+    }
+
+    /// Time the file was started at, in milliseconds since the epoch.
+    pub fn upload_timestamp(&self) -> i64 {
+        self.upload_timestamp
+    }
+
+    /// Parts uploaded so far, ordered by part number.
+    pub fn parts(&self) -> Vec<Part> {
+        let guard = self.parts.lock().unwrap();
+        guard.values().cloned().collect()
+    }
+
+    /// Records a part uploaded earlier, e.g. by another process, so that it
+    /// is included when the file is finished.
+    pub fn add_part(&self, part: Part) {
+        let mut guard = self.parts.lock().unwrap();
+        guard.insert(part.number, part);
+    }
+
+    /// Part number following the highest one uploaded so far.
+    pub fn next_part_number(&self) -> u16 {
+        let guard = self.parts.lock().unwrap();
+        guard.keys().next_back().map_or(1, |n| n + 1)
+    }
+
+    pub(crate) fn part_sha1_array(&self) -> Vec<String> {
+        let guard = self.parts.lock().unwrap();
+        guard.values().map(|p| p.sha1.clone()).collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Part {
+    pub number: u16,
+    pub sha1: String,
+    pub size: u64,
+}
+
+impl From<UploadPartResponse> for Part {
+    fn from(res: UploadPartResponse) -> Self {
+        Self {
+            number: res.part_number,
+            sha1: res.content_sha1,
+            size: res.content_length,
+        }
+    }
+}
+
+/// Upload URL and token for the parts of one large file.
+///
+/// B2 allows only one upload at a time per URL, concurrent part uploads
+/// need a URL each.
+#[derive(Clone, Debug)]
+pub struct UploadPartUrl {
+    pub(crate) url: String,
+    pub(crate) token: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StartLargeFileRequest {
+    pub bucket_id: String,
+    pub file_name: String,
+    pub content_type: String,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub file_info: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_side_encryption: Option<EncryptionRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_retention: Option<FileRetention>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub legal_hold: Option<LegalHold>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LargeFileResponse {
+    pub file_id: String,
+    pub file_name: String,
+    pub content_type: String,
+    #[serde(default)]
+    pub file_info: HashMap<String, String>,
+    pub upload_timestamp: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GetUploadPartUrlRequest {
+    pub file_id: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GetUploadPartUrlResponse {
+    pub upload_url: String,
+    pub authorization_token: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UploadPartResponse {
+    pub part_number: u16,
+    pub content_length: u64,
+    pub content_sha1: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FinishLargeFileRequest {
+    pub file_id: String,
+    pub part_sha1_array: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CancelLargeFileRequest {
+    pub file_id: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListUnfinishedLargeFilesRequest {
+    bucket_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name_prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_file_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_file_count: Option<usize>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListUnfinishedLargeFilesResponse {
+    files: Vec<LargeFileResponse>,
+    next_file_id: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ListUnfinishedLargeFilesBuilder {
+    inner: Client,
+    bucket_id: String,
+    name_prefix: Option<String>,
+    start_file_id: Option<String>,
+    max_file_count: Option<usize>,
+}
+
+impl ListUnfinishedLargeFilesBuilder {
+    pub(crate) fn new<T: AsRef<str>>(client: Client, bucket_id: T) -> Self {
+        Self {
+            inner: client,
+            bucket_id: bucket_id.as_ref().to_string(),
+            name_prefix: Default::default(),
+            start_file_id: Default::default(),
+            max_file_count: Default::default(),
+        }
+    }
+
+    pub fn name_prefix<T: AsRef<str>>(&mut self, name_prefix: T) -> &mut Self {
+        self.name_prefix = Some(name_prefix.as_ref().to_string());
+        self
+    }
+
+    pub fn start_file_id<T: AsRef<str>>(&mut self, start_file_id: T) -> &mut Self {
+        self.start_file_id = Some(start_file_id.as_ref().to_string());
+        self
+    }
+
+    pub fn max_file_count(&mut self, max_file_count: usize) -> &mut Self {
+        self.max_file_count = Some(max_file_count);
+        self
+    }
+
+    /// Returns one page of unfinished large files and the id to start the
+    /// next page at, if there is one.
+    pub async fn send(&mut self) -> Result<(Vec<LargeFile>, Option<String>)> {
+        let req = ListUnfinishedLargeFilesRequest {
+            bucket_id: self.bucket_id.clone(),
+            name_prefix: self.name_prefix.clone(),
+            start_file_id: self.start_file_id.clone(),
+            max_file_count: self.max_file_count,
+        };
+
+        let res = self.inner._list_unfinished_large_files(req).await?;
+
+        Ok((
+            res.files
+                .into_iter()
+                .map(LargeFile::from_response)
+                .collect(),
+            res.next_file_id,
+        ))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListPartsRequest {
+    file_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_part_number: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_part_count: Option<usize>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListPartsResponse {
+    parts: Vec<UploadPartResponse>,
+    next_part_number: Option<u16>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ListPartsBuilder {
+    inner: Client,
+    file_id: String,
+    start_part_number: Option<u16>,
+    max_part_count: Option<usize>,
+}
+
+impl ListPartsBuilder {
+    pub(crate) fn new<T: AsRef<str>>(client: Client, file_id: T) -> Self {
+        Self {
+            inner: client,
+            file_id: file_id.as_ref().to_string(),
+            start_part_number: Default::default(),
+            max_part_count: Default::default(),
+        }
+    }
+
+    pub fn start_part_number(&mut self, start_part_number: u16) -> &mut Self {
+        self.start_part_number = Some(start_part_number);
+        self
+    }
+
+    pub fn max_part_count(&mut self, max_part_count: usize) -> &mut Self {
+        self.max_part_count = Some(max_part_count);
+        self
+    }
+
+    /// Returns one page of uploaded parts and the part number to start the
+    /// next page at, if there is one.
+    pub async fn send(&mut self) -> Result<(Vec<Part>, Option<u16>)> {
+        let req = ListPartsRequest {
+            file_id: self.file_id.clone(),
+            start_part_number: self.start_part_number,
+            max_part_count: self.max_part_count,
+        };
+
+        let res = self.inner._list_parts(req).await?;
+
+        Ok((
+            res.parts.into_iter().map(From::from).collect(),
+            res.next_part_number,
+        ))
+    }
+}
@@ -1,11 +1,17 @@
 use std::ops::{Deref, DerefMut};
 
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
 
 use super::File;
 use crate::bucket::UploadFileResponse;
+use crate::paging::{self, Paged};
 use crate::{Client, Result};
 
+/// Largest page a limited listing asks for, B2 bills every 1000 files
+/// returned as a separate call.
+const MAX_PAGE_SIZE: usize = 1000;
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct NextFileName(String);
 
@@ -88,6 +94,7 @@ pub struct ListFileNamesBuilder {
     max_file_count: Option<usize>,
     prefix: Option<String>,
     delimeter: Option<String>,
+    limit: Option<usize>,
 }
 
 impl ListFileNamesBuilder {
@@ -99,6 +106,7 @@ impl ListFileNamesBuilder {
             max_file_count: Default::default(),
             prefix: Default::default(),
             delimeter: Default::default(),
+            limit: Default::default(),
         }
     }
 
@@ -122,21 +130,267 @@ impl ListFileNamesBuilder {
         self
     }
 
+    /// Total number of files [`ListFileNamesBuilder::into_stream`] yields,
+    /// [`ListFileNamesBuilder::send`] is not affected.
+    pub fn limit(&mut self, limit: usize) -> &mut Self {
+        self.limit = Some(limit);
+        self
+    }
+
     pub async fn send(&mut self) -> Result<(Vec<File>, Option<NextFileName>)> {
+        self.fetch_page(self.max_file_count).await
+    }
+
+    /// Lists all files, see [`ListFileNamesBuilder::limit`] to stop early.
+    pub fn into_stream(self) -> impl Stream<Item = Result<File>> {
+        paging::into_stream(self)
+    }
+}
+
+impl Paged for ListFileNamesBuilder {
+    type Item = File;
+    type Next = NextFileName;
+
+    const MAX_PAGE_SIZE: usize = MAX_PAGE_SIZE;
+
+    fn max_count(&self) -> Option<usize> {
+        self.max_file_count
+    }
+
+    fn remaining(&mut self) -> &mut Option<usize> {
+        &mut self.limit
+    }
+
+    async fn fetch_page(
+        &self,
+        max_file_count: Option<usize>,
+    ) -> Result<(Vec<File>, Option<NextFileName>)> {
+        // Listings of a key restricted to a prefix have to stay within it.
+        let prefix = self.prefix.as_deref().unwrap_or_default();
+        self.inner
+            .check_allowed(Some(&self.bucket_id), None, Some(prefix))
+            .await?;
+
         let req = ListFileNamesRequest {
             bucket_id: self.bucket_id.clone(),
             start_file_name: self.start_file_name.clone(),
-            max_file_count: self.max_file_count.clone(),
+            max_file_count,
             prefix: self.prefix.clone(),
             delimeter: self.delimeter.clone(),
         };
 
         let res = self.inner._list_file_names(req).await?;
-        let next_file_name = res.next_file_name.map(|s| NextFileName(s));
+        let next_file_name = res.next_file_name.map(NextFileName);
 
         Ok((
             res.files.into_iter().map(From::from).collect(),
             next_file_name,
         ))
     }
+
+    fn seek(&mut self, next: NextFileName) {
+        self.start_file_name = Some(next.0);
+    }
+}
+
+/// Where a listing of file versions continues, pass both to
+/// [`ListFileVersionsBuilder::start_file_name`] and
+/// [`ListFileVersionsBuilder::start_file_id`] for the next page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NextFileVersion {
+    pub file_name: String,
+    pub file_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListFileVersionsRequest {
+    bucket_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_file_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_file_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_file_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delimiter: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListFileVersionsResponse {
+    files: Vec<UploadFileResponse>,
+    next_file_name: Option<String>,
+    next_file_id: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ListFileVersionsBuilder {
+    inner: Client,
+    bucket_id: String,
+    start_file_name: Option<String>,
+    start_file_id: Option<String>,
+    max_file_count: Option<usize>,
+    prefix: Option<String>,
+    delimiter: Option<String>,
+    limit: Option<usize>,
+}
+
+impl ListFileVersionsBuilder {
+    pub(crate) fn new<T: AsRef<str>>(client: Client, bucket_id: T) -> Self {
+        Self {
+            inner: client,
+            bucket_id: bucket_id.as_ref().to_string(),
+            start_file_name: Default::default(),
+            start_file_id: Default::default(),
+            max_file_count: Default::default(),
+            prefix: Default::default(),
+            delimiter: Default::default(),
+            limit: Default::default(),
+        }
+    }
+
+    pub fn start_file_name<T: AsRef<str>>(&mut self, start_file_name: T) -> &mut Self {
+        self.start_file_name = Some(start_file_name.as_ref().to_string());
+        self
+    }
+
+    /// Only valid together with [`ListFileVersionsBuilder::start_file_name`].
+    pub fn start_file_id<T: AsRef<str>>(&mut self, start_file_id: T) -> &mut Self {
+        self.start_file_id = Some(start_file_id.as_ref().to_string());
+        self
+    }
+
+    pub fn max_file_count(&mut self, max_file_count: usize) -> &mut Self {
+        self.max_file_count = Some(max_file_count);
+        self
+    }
+
+    pub fn prefix<T: AsRef<str>>(&mut self, prefix: T) -> &mut Self {
+        self.prefix = Some(prefix.as_ref().to_string());
+        self
+    }
+
+    pub fn delimiter<T: AsRef<str>>(&mut self, delimiter: T) -> &mut Self {
+        self.delimiter = Some(delimiter.as_ref().to_string());
+        self
+    }
+
+    /// Total number of versions [`ListFileVersionsBuilder::into_stream`]
+    /// yields, [`ListFileVersionsBuilder::send`] is not affected.
+    pub fn limit(&mut self, limit: usize) -> &mut Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Returns one page of file versions, newest first for each name, and
+    /// where the next page starts, if there is one.
+    pub async fn send(&mut self) -> Result<(Vec<File>, Option<NextFileVersion>)> {
+        self.fetch_page(self.max_file_count).await
+    }
+
+    /// Lists all file versions, see [`ListFileVersionsBuilder::limit`] to
+    /// stop early.
+    pub fn into_stream(self) -> impl Stream<Item = Result<File>> {
+        paging::into_stream(self)
+    }
+}
+
+impl Paged for ListFileVersionsBuilder {
+    type Item = File;
+    type Next = NextFileVersion;
+
+    const MAX_PAGE_SIZE: usize = MAX_PAGE_SIZE;
+
+    fn max_count(&self) -> Option<usize> {
+        self.max_file_count
+    }
+
+    fn remaining(&mut self) -> &mut Option<usize> {
+        &mut self.limit
+    }
+
+    async fn fetch_page(
+        &self,
+        max_file_count: Option<usize>,
+    ) -> Result<(Vec<File>, Option<NextFileVersion>)> {
+        let prefix = self.prefix.as_deref().unwrap_or_default();
+        self.inner
+            .check_allowed(Some(&self.bucket_id), None, Some(prefix))
+            .await?;
+
+        let req = ListFileVersionsRequest {
+            bucket_id: self.bucket_id.clone(),
+            start_file_name: self.start_file_name.clone(),
+            start_file_id: self.start_file_id.clone(),
+            max_file_count,
+            prefix: self.prefix.clone(),
+            delimiter: self.delimiter.clone(),
+        };
+
+        let res = self.inner._list_file_versions(req).await?;
+        let next = res.next_file_name.map(|file_name| NextFileVersion {
+            file_name,
+            file_id: res.next_file_id,
+        });
+
+        Ok((res.files.into_iter().map(From::from).collect(), next))
+    }
+
+    fn seek(&mut self, next: NextFileVersion) {
+        self.start_file_name = Some(next.file_name);
+        self.start_file_id = next.file_id;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::{File, FileAction};
+
+    #[test]
+    fn folder_entries_deserialize() {
+        let res: ListFileVersionsResponse = serde_json::from_str(
+            r#"{
+                "files": [
+                    {
+                        "accountId": "acc",
+                        "action": "folder",
+                        "bucketId": "bucket",
+                        "contentLength": 0,
+                        "contentSha1": null,
+                        "contentType": null,
+                        "fileId": null,
+                        "fileInfo": {},
+                        "fileName": "photos/",
+                        "uploadTimestamp": 0
+                    },
+                    {
+                        "accountId": "acc",
+                        "action": "upload",
+                        "bucketId": "bucket",
+                        "contentLength": 5,
+                        "contentSha1": "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d",
+                        "contentType": "text/plain",
+                        "fileId": "4_z27c88f1d182b150646ff0b16_f1004ba650fe24e6b_d20150809_m012853_c100_v0009990_t0000",
+                        "fileInfo": {},
+                        "fileName": "readme.txt",
+                        "uploadTimestamp": 1439083733000
+                    }
+                ],
+                "nextFileName": null,
+                "nextFileId": null
+            }"#,
+        )
+        .unwrap();
+
+        let files: Vec<File> = res.files.into_iter().map(File::from).collect();
+        assert_eq!(files[0].action, FileAction::Folder);
+        assert_eq!(files[0].name, "photos/");
+        assert!(files[0].id.is_empty());
+        assert_eq!(files[1].action, FileAction::Upload);
+        assert!(files[1].id.starts_with("4_z27c88f1d"));
+    }
 }
@@ -0,0 +1,150 @@
+use std::time::SystemTime;
+
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::bucket::RetentionMode;
+use crate::{Client, Result};
+
+/// Object Lock retention of a file version, which cannot be deleted before
+/// `retain_until_timestamp` while it is set.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRetention {
+    pub mode: Option<RetentionMode>,
+    /// Milliseconds since the epoch.
+    pub retain_until_timestamp: Option<i64>,
+}
+
+impl FileRetention {
+    pub fn new(mode: RetentionMode, retain_until: SystemTime) -> Self {
+        let retain_until_timestamp = retain_until
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX));
+
+        Self {
+            mode: Some(mode),
+            retain_until_timestamp: Some(retain_until_timestamp),
+        }
+    }
+
+    /// Adds the headers that set this retention on an upload.
+    pub(crate) fn apply(&self, mut req: RequestBuilder) -> RequestBuilder {
+        if let Some(mode) = self.mode {
+            let mode = match mode {
+                RetentionMode::Governance => "governance",
+                RetentionMode::Compliance => "compliance",
+            };
+            req = req.header("X-Bz-File-Retention-Mode", mode);
+        }
+        if let Some(timestamp) = self.retain_until_timestamp {
+            req = req.header("X-Bz-File-Retention-Retain-Until-Timestamp", timestamp);
+        }
+        req
+    }
+}
+
+/// Object Lock legal hold of a file version, which cannot be deleted while
+/// the hold is on regardless of its retention.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LegalHold {
+    On,
+    Off,
+}
+
+impl LegalHold {
+    /// Adds the header that sets this legal hold on an upload.
+    pub(crate) fn apply(&self, req: RequestBuilder) -> RequestBuilder {
+        let value = match self {
+            Self::On => "on",
+            Self::Off => "off",
+        };
+        req.header("X-Bz-File-Legal-Hold", value)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateFileRetentionRequest {
+    file_name: String,
+    file_id: String,
+    file_retention: FileRetention,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    bypass_governance: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateFileRetentionResponse {
+    file_retention: FileRetention,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateFileLegalHoldRequest {
+    pub file_name: String,
+    pub file_id: String,
+    pub legal_hold: LegalHold,
+}
+
+/// Changes the retention of a file version in a bucket with Object Lock
+/// enabled.
+///
+/// Retention can always be extended. Shortening or removing governance mode
+/// retention requires [`UpdateFileRetentionBuilder::bypass_governance`],
+/// compliance mode retention cannot be shortened at all.
+#[derive(Clone, Debug)]
+pub struct UpdateFileRetentionBuilder {
+    inner: Client,
+    file_name: String,
+    file_id: String,
+    file_retention: FileRetention,
+    bypass_governance: bool,
+}
+
+impl UpdateFileRetentionBuilder {
+    pub(crate) fn new<T, U>(
+        client: Client,
+        file_name: T,
+        file_id: U,
+        file_retention: FileRetention,
+    ) -> Self
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+    {
+        Self {
+            inner: client,
+            file_name: file_name.as_ref().to_string(),
+            file_id: file_id.as_ref().to_string(),
+            file_retention,
+            bypass_governance: Default::default(),
+        }
+    }
+
+    /// Allows shortening or removing governance mode retention, which
+    /// requires the `bypassGovernance` capability.
+    pub fn bypass_governance(&mut self, bypass_governance: bool) -> &mut Self {
+        self.bypass_governance = bypass_governance;
+        self
+    }
+
+    /// Returns the retention the file version has now.
+    pub async fn send(&mut self) -> Result<FileRetention> {
+        self.inner
+            .check_allowed(None, None, Some(&self.file_name))
+            .await?;
+
+        let req = UpdateFileRetentionRequest {
+            file_name: self.file_name.clone(),
+            file_id: self.file_id.clone(),
+            file_retention: self.file_retention.clone(),
+            bypass_governance: self.bypass_governance,
+        };
+
+        let res = self.inner._update_file_retention(req).await?;
+
+        Ok(res.file_retention)
+    }
+}
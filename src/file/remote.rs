@@ -0,0 +1,193 @@
+use std::future::Future;
+use std::io::{self, SeekFrom};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+use super::{ByteRange, CustomerKey, Download, DownloadFileRequest, DownloadTarget};
+use crate::{Client, Error, Result};
+
+const DEFAULT_READ_AHEAD: usize = 1024 * 1024;
+
+type FetchFuture = Pin<Box<dyn Future<Output = Result<Bytes>> + Send>>;
+
+/// A file stored in B2 that can be read and seeked like a local one.
+///
+/// Reads are served from an in-memory buffer which is refilled with a ranged
+/// download whenever the position leaves it, fetching at least
+/// [`RemoteFile::read_ahead`] bytes at a time. Ranges are requested by file
+/// id, so the contents stay the same even if a newer version is uploaded
+/// under the same name while the file is open. Files encrypted with SSE-C
+/// are opened with the customer key, which is then sent along with every
+/// range.
+pub struct RemoteFile {
+    client: Client,
+    customer_key: Option<CustomerKey>,
+    id: String,
+    name: String,
+    size: u64,
+    pos: u64,
+    read_ahead: usize,
+    buf: Bytes,
+    buf_start: u64,
+    fetch: Option<(u64, FetchFuture)>,
+}
+
+impl RemoteFile {
+    pub(crate) async fn open(
+        client: Client,
+        target: DownloadTarget,
+        customer_key: Option<CustomerKey>,
+    ) -> Result<Self> {
+        let req = DownloadFileRequest {
+            target,
+            range: None,
+            head: true,
+            customer_key: customer_key.clone(),
+        };
+        let res = client._download_file(req).await?;
+        let download = Download::from_response(res);
+
+        Ok(Self {
+            client,
+            customer_key,
+            id: download.id().to_string(),
+            name: download.name().to_string(),
+            size: download.file_size(),
+            pos: 0,
+            read_ahead: DEFAULT_READ_AHEAD,
+            buf: Bytes::new(),
+            buf_start: 0,
+            fetch: None,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Minimum number of bytes requested from B2 when the buffer runs out,
+    /// defaults to 1 MiB.
+    pub fn read_ahead(&self) -> usize {
+        self.read_ahead
+    }
+
+    pub fn set_read_ahead(&mut self, read_ahead: usize) -> &mut Self {
+        self.read_ahead = read_ahead.max(1);
+        self
+    }
+
+    fn buffered(&self) -> Option<&[u8]> {
+        let offset = self.pos.checked_sub(self.buf_start)?;
+        let offset = usize::try_from(offset).ok()?;
+
+        self.buf.get(offset..).filter(|b| !b.is_empty())
+    }
+
+    fn fetch(&self, start: u64, len: u64) -> FetchFuture {
+        let client = self.client.clone();
+        let req = DownloadFileRequest {
+            target: DownloadTarget::Id(self.id.clone()),
+            range: Some(ByteRange {
+                start,
+                end: Some(start.saturating_add(len).min(self.size) - 1),
+            }),
+            head: false,
+            customer_key: self.customer_key.clone(),
+        };
+
+        Box::pin(async move {
+            let res = client._download_file(req).await?;
+            res.bytes().await.map_err(Error::from)
+        })
+    }
+}
+
+impl ::std::fmt::Debug for RemoteFile {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        f.debug_struct("RemoteFile")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("size", &self.size)
+            .field("pos", &self.pos)
+            .field("read_ahead", &self.read_ahead)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AsyncRead for RemoteFile {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.pos >= this.size || buf.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+
+            if let Some(buffered) = this.buffered() {
+                let n = buffered.len().min(buf.remaining());
+                buf.put_slice(&buffered[..n]);
+                this.pos += n as u64;
+                return Poll::Ready(Ok(()));
+            }
+
+            // A fetch started before a seek is of no use anymore.
+            if !matches!(this.fetch, Some((start, _)) if start == this.pos) {
+                let len = this.read_ahead.max(buf.remaining()) as u64;
+                this.fetch = Some((this.pos, this.fetch(this.pos, len)));
+            }
+
+            let (start, fetch) = this.fetch.as_mut().unwrap();
+            let res = ready!(fetch.as_mut().poll(cx));
+            let start = *start;
+            this.fetch = None;
+
+            let bytes = res.map_err(io::Error::other)?;
+            if bytes.is_empty() {
+                return Poll::Ready(Ok(()));
+            }
+            this.buf = bytes;
+            this.buf_start = start;
+        }
+    }
+}
+
+impl AsyncSeek for RemoteFile {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        let pos = match position {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => this.size.checked_add_signed(n),
+            SeekFrom::Current(n) => this.pos.checked_add_signed(n),
+        };
+
+        match pos {
+            Some(pos) => {
+                this.pos = pos;
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.pos))
+    }
+}
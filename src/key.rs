@@ -0,0 +1,100 @@
+mod create;
+mod list;
+
+pub use self::create::CreateKeyBuilder;
+pub(crate) use self::create::*;
+pub use self::list::ListKeysBuilder;
+pub(crate) use self::list::*;
+
+use serde::{Deserialize, Serialize};
+
+/// What an application key is allowed to do.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Capability {
+    ListKeys,
+    WriteKeys,
+    DeleteKeys,
+    ListBuckets,
+    ListAllBucketNames,
+    ReadBuckets,
+    WriteBuckets,
+    DeleteBuckets,
+    ReadBucketEncryption,
+    WriteBucketEncryption,
+    ReadBucketRetentions,
+    WriteBucketRetentions,
+    ReadFileRetentions,
+    WriteFileRetentions,
+    ReadFileLegalHolds,
+    WriteFileLegalHolds,
+    ReadBucketReplications,
+    WriteBucketReplications,
+    ReadBucketLogging,
+    WriteBucketLogging,
+    ReadBucketNotifications,
+    WriteBucketNotifications,
+    BypassGovernance,
+    ListFiles,
+    ReadFiles,
+    ShareFiles,
+    WriteFiles,
+    DeleteFiles,
+    /// A capability this crate does not know about yet.
+    #[serde(untagged)]
+    Other(String),
+}
+
+/// An application key, without its secret.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Key {
+    #[serde(rename = "applicationKeyId")]
+    pub id: String,
+    #[serde(rename = "keyName")]
+    pub name: String,
+    pub capabilities: Vec<Capability>,
+    pub account_id: String,
+    /// Milliseconds since the epoch, `None` if the key does not expire.
+    pub expiration_timestamp: Option<i64>,
+    /// The only bucket the key can access, `None` if it can access all.
+    pub bucket_id: Option<String>,
+    /// The key can only access files whose name starts with this.
+    pub name_prefix: Option<String>,
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+/// A key returned by [`CreateKeyBuilder::send`], the only time B2 hands out
+/// its secret.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CreatedKey {
+    #[serde(flatten)]
+    pub key: Key,
+    #[serde(rename = "applicationKey")]
+    pub secret: KeySecret,
+}
+
+/// The secret of an application key. It is never printed by `Debug`.
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct KeySecret(String);
+
+impl KeySecret {
+    pub fn expose(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl ::std::fmt::Debug for KeySecret {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        f.debug_tuple("KeySecret").field(&"..").finish()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeleteKeyRequest {
+    pub application_key_id: String,
+}
@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::{Capability, CreatedKey};
+use crate::error::ErrorKind;
+use crate::{Client, Error, Result};
+
+const MAX_KEY_NAME_LEN: usize = 100;
+const MAX_VALID_DURATION: Duration = Duration::from_secs(1000 * 24 * 60 * 60);
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateKeyRequest {
+    pub account_id: String,
+    capabilities: Vec<Capability>,
+    key_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    valid_duration_in_seconds: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bucket_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name_prefix: Option<String>,
+}
+
+/// Creates an application key, which can be restricted to a bucket and to
+/// files under a name prefix in it.
+#[derive(Clone, Debug)]
+pub struct CreateKeyBuilder {
+    inner: Client,
+    key_name: String,
+    capabilities: Vec<Capability>,
+    valid_duration: Option<Duration>,
+    bucket_id: Option<String>,
+    name_prefix: Option<String>,
+}
+
+impl CreateKeyBuilder {
+    pub(crate) fn new<T, I>(client: Client, key_name: T, capabilities: I) -> Self
+    where
+        T: AsRef<str>,
+        I: IntoIterator<Item = Capability>,
+    {
+        Self {
+            inner: client,
+            key_name: key_name.as_ref().to_string(),
+            capabilities: capabilities.into_iter().collect(),
+            valid_duration: Default::default(),
+            bucket_id: Default::default(),
+            name_prefix: Default::default(),
+        }
+    }
+
+    /// How long the key is valid, at most 1000 days. Keys without one never
+    /// expire.
+    pub fn valid_duration(&mut self, valid_duration: Duration) -> &mut Self {
+        self.valid_duration = Some(valid_duration);
+        self
+    }
+
+    /// Restricts the key to a single bucket.
+    pub fn bucket_id<T: AsRef<str>>(&mut self, bucket_id: T) -> &mut Self {
+        self.bucket_id = Some(bucket_id.as_ref().to_string());
+        self
+    }
+
+    /// Restricts the key to files whose name starts with `name_prefix`,
+    /// requires [`CreateKeyBuilder::bucket_id`].
+    pub fn name_prefix<T: AsRef<str>>(&mut self, name_prefix: T) -> &mut Self {
+        self.name_prefix = Some(name_prefix.as_ref().to_string());
+        self
+    }
+
+    pub async fn send(&mut self) -> Result<CreatedKey> {
+        self.validate()?;
+
+        let req = CreateKeyRequest {
+            account_id: Default::default(),
+            capabilities: self.capabilities.clone(),
+            key_name: self.key_name.clone(),
+            valid_duration_in_seconds: self.valid_duration.map(|d| d.as_secs()),
+            bucket_id: self.bucket_id.clone(),
+            name_prefix: self.name_prefix.clone(),
+        };
+
+        self.inner._create_key(req).await
+    }
+
+    fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Err(Error::new(ErrorKind::BadRequest, message));
+
+        let name_ok = (1..=MAX_KEY_NAME_LEN).contains(&self.key_name.len())
+            && self
+                .key_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if !name_ok {
+            return invalid(format!(
+                "key name `{}` must be 1 to {} letters, digits and `-`",
+                self.key_name, MAX_KEY_NAME_LEN
+            ));
+        }
+        if self.capabilities.is_empty() {
+            return invalid(format!("key `{}` has no capabilities", self.key_name));
+        }
+        if let Some(valid_duration) = self.valid_duration {
+            if valid_duration.as_secs() == 0 || valid_duration > MAX_VALID_DURATION {
+                return invalid(format!(
+                    "key `{}` must be valid for at least a second and at most 1000 days",
+                    self.key_name
+                ));
+            }
+        }
+        if self.name_prefix.is_some() && self.bucket_id.is_none() {
+            return invalid(format!(
+                "key `{}` has a name prefix but is not restricted to a bucket",
+                self.key_name
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{client, fails_with};
+
+    fn builder(name: &str, capabilities: &[Capability]) -> CreateKeyBuilder {
+        CreateKeyBuilder::new(client(), name, capabilities.iter().cloned())
+    }
+
+    #[test]
+    fn key_names_are_validated() {
+        let caps = [Capability::ReadFiles];
+
+        assert!(builder("backup-key-1", &caps).validate().is_ok());
+        assert!(builder(&"a".repeat(MAX_KEY_NAME_LEN), &caps)
+            .validate()
+            .is_ok());
+        assert!(fails_with(
+            builder("", &caps).validate(),
+            ErrorKind::BadRequest
+        ));
+        assert!(fails_with(
+            builder(&"a".repeat(MAX_KEY_NAME_LEN + 1), &caps).validate(),
+            ErrorKind::BadRequest
+        ));
+        assert!(fails_with(
+            builder("backup key", &caps).validate(),
+            ErrorKind::BadRequest
+        ));
+        assert!(fails_with(
+            builder("backup_key", &caps).validate(),
+            ErrorKind::BadRequest
+        ));
+        assert!(fails_with(
+            builder("schlüssel", &caps).validate(),
+            ErrorKind::BadRequest
+        ));
+    }
+
+    #[test]
+    fn capabilities_are_required() {
+        assert!(fails_with(
+            builder("key", &[]).validate(),
+            ErrorKind::BadRequest
+        ));
+    }
+
+    #[test]
+    fn valid_duration_is_bounded() {
+        let mut b = builder("key", &[Capability::ReadFiles]);
+
+        b.valid_duration(Duration::from_secs(1));
+        assert!(b.validate().is_ok());
+        b.valid_duration(MAX_VALID_DURATION);
+        assert!(b.validate().is_ok());
+
+        b.valid_duration(Duration::ZERO);
+        assert!(fails_with(b.validate(), ErrorKind::BadRequest));
+        b.valid_duration(Duration::from_millis(999));
+        assert!(fails_with(b.validate(), ErrorKind::BadRequest));
+        b.valid_duration(MAX_VALID_DURATION + Duration::from_secs(1));
+        assert!(fails_with(b.validate(), ErrorKind::BadRequest));
+    }
+
+    #[test]
+    fn name_prefix_requires_bucket() {
+        let mut b = builder("key", &[Capability::ReadFiles]);
+
+        b.name_prefix("photos/");
+        assert!(fails_with(b.validate(), ErrorKind::BadRequest));
+
+        b.bucket_id("bucket-id");
+        assert!(b.validate().is_ok());
+    }
+}
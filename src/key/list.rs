@@ -0,0 +1,106 @@
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+
+use super::Key;
+use crate::paging::{self, Paged};
+use crate::{Client, Result};
+
+/// Most keys B2 returns in a single call.
+const MAX_KEY_COUNT: usize = 10_000;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListKeysRequest {
+    pub account_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_key_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_application_key_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListKeysResponse {
+    keys: Vec<Key>,
+    next_application_key_id: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ListKeysBuilder {
+    inner: Client,
+    max_key_count: Option<usize>,
+    start_application_key_id: Option<String>,
+    limit: Option<usize>,
+}
+
+impl ListKeysBuilder {
+    pub(crate) fn new(client: Client) -> Self {
+        Self {
+            inner: client,
+            max_key_count: Default::default(),
+            start_application_key_id: Default::default(),
+            limit: Default::default(),
+        }
+    }
+
+    pub fn max_key_count(&mut self, max_key_count: usize) -> &mut Self {
+        self.max_key_count = Some(max_key_count);
+        self
+    }
+
+    pub fn start_application_key_id<T: AsRef<str>>(
+        &mut self,
+        start_application_key_id: T,
+    ) -> &mut Self {
+        self.start_application_key_id = Some(start_application_key_id.as_ref().to_string());
+        self
+    }
+
+    /// Total number of keys [`ListKeysBuilder::into_stream`] yields,
+    /// [`ListKeysBuilder::send`] is not affected.
+    pub fn limit(&mut self, limit: usize) -> &mut Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Returns a page of keys and the id of the key the next page starts at.
+    pub async fn send(&mut self) -> Result<(Vec<Key>, Option<String>)> {
+        self.fetch_page(self.max_key_count).await
+    }
+
+    /// Lists all keys, see [`ListKeysBuilder::limit`] to stop early.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Key>> {
+        paging::into_stream(self)
+    }
+}
+
+impl Paged for ListKeysBuilder {
+    type Item = Key;
+    type Next = String;
+
+    const MAX_PAGE_SIZE: usize = MAX_KEY_COUNT;
+
+    fn max_count(&self) -> Option<usize> {
+        self.max_key_count
+    }
+
+    fn remaining(&mut self) -> &mut Option<usize> {
+        &mut self.limit
+    }
+
+    async fn fetch_page(&self, max_key_count: Option<usize>) -> Result<(Vec<Key>, Option<String>)> {
+        let req = ListKeysRequest {
+            account_id: Default::default(),
+            max_key_count,
+            start_application_key_id: self.start_application_key_id.clone(),
+        };
+
+        let res = self.inner._list_keys(req).await?;
+
+        Ok((res.keys, res.next_application_key_id))
+    }
+
+    fn seek(&mut self, next: String) {
+        self.start_application_key_id = Some(next);
+    }
+}
@@ -2,12 +2,35 @@ mod account;
 pub mod bucket;
 mod client;
 pub mod file;
+pub mod key;
+mod paging;
+mod retry;
 
 pub(crate) mod error;
 
 #[doc(inline)]
 pub use bucket::Bucket;
-pub use client::Client;
-pub use error::{Error, Result};
+pub use client::{Client, ClientBuilder};
+pub use error::{Error, ErrorKind, Result};
+pub use retry::RetryPolicy;
 
 pub(crate) use account::Account;
+pub use account::AccountInfo;
+
+/// Helpers shared by the unit tests of all modules.
+#[cfg(test)]
+pub(crate) mod test_util {
+    use std::mem::discriminant;
+
+    use crate::{Client, ErrorKind, Result};
+
+    /// A client that has not been authorized, for building requests.
+    pub(crate) fn client() -> Client {
+        Client::builder("id".into(), "key".into()).build().unwrap()
+    }
+
+    /// Whether `res` failed with an error of `kind`.
+    pub(crate) fn fails_with<T>(res: Result<T>, kind: ErrorKind) -> bool {
+        matches!(res, Err(err) if discriminant(&err.kind()) == discriminant(&kind))
+    }
+}
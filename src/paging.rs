@@ -0,0 +1,168 @@
+use std::future::Future;
+
+use futures_util::{stream, Stream, TryStreamExt};
+
+use crate::Result;
+
+/// Items of one page and where the following page starts, if there is one.
+pub(crate) type Page<T, N> = (Vec<T>, Option<N>);
+
+/// A listing B2 returns in pages, each one continuing where the previous
+/// one ended.
+pub(crate) trait Paged: Sized {
+    type Item;
+    /// Where the following page starts.
+    type Next;
+
+    /// Largest page a limited listing asks for.
+    const MAX_PAGE_SIZE: usize;
+
+    /// Page size asked for by the caller, `None` to let B2 decide.
+    fn max_count(&self) -> Option<usize>;
+
+    /// Number of items left to yield, `None` if the listing is unlimited.
+    fn remaining(&mut self) -> &mut Option<usize>;
+
+    /// Fetches the page of at most `max_count` items the listing points at.
+    fn fetch_page(
+        &self,
+        max_count: Option<usize>,
+    ) -> impl Future<Output = Result<Page<Self::Item, Self::Next>>> + Send;
+
+    /// Points the listing at the page starting at `next`.
+    fn seek(&mut self, next: Self::Next);
+}
+
+/// Streams all items of `listing`, fetching the next page whenever the
+/// previous one has been consumed. Dropping the stream stops the listing.
+pub(crate) fn into_stream<P: Paged>(listing: P) -> impl Stream<Item = Result<P::Item>> {
+    stream::try_unfold(Some(listing), |listing| async move {
+        match listing {
+            Some(listing) => next_page(listing).await,
+            None => Ok(None),
+        }
+    })
+    .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+    .try_flatten()
+}
+
+/// Fetches the page `listing` points at, returning the listing moved on to
+/// the following page unless this was the last one.
+async fn next_page<P: Paged>(mut listing: P) -> Result<Option<Page<P::Item, P>>> {
+    if *listing.remaining() == Some(0) {
+        return Ok(None);
+    }
+
+    let max_count = page_size(listing.max_count(), *listing.remaining(), P::MAX_PAGE_SIZE);
+    let (mut items, next) = listing.fetch_page(max_count).await?;
+    if let Some(remaining) = listing.remaining().as_mut() {
+        items.truncate(*remaining);
+        *remaining -= items.len();
+    }
+    let next = next.map(|next| {
+        listing.seek(next);
+        listing
+    });
+
+    Ok(Some((items, next)))
+}
+
+/// Number of items to ask for in the next page of a listing limited to
+/// `remaining` more items, so that no more than that are fetched. Pages of
+/// unlimited listings are `max_count` or as big as B2 decides, pages of
+/// limited ones at most `max_page_size`.
+pub(crate) fn page_size(
+    max_count: Option<usize>,
+    remaining: Option<usize>,
+    max_page_size: usize,
+) -> Option<usize> {
+    match (max_count, remaining) {
+        (Some(count), Some(remaining)) => Some(count.min(remaining)),
+        (None, Some(remaining)) => Some(remaining.min(max_page_size)),
+        (count, None) => count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Numbers `0..len`, recording the page sizes asked for.
+    struct Numbers {
+        start: usize,
+        len: usize,
+        limit: Option<usize>,
+        asked: Arc<Mutex<Vec<Option<usize>>>>,
+    }
+
+    impl Paged for Numbers {
+        type Item = usize;
+        type Next = usize;
+
+        const MAX_PAGE_SIZE: usize = 10;
+
+        fn max_count(&self) -> Option<usize> {
+            None
+        }
+
+        fn remaining(&mut self) -> &mut Option<usize> {
+            &mut self.limit
+        }
+
+        async fn fetch_page(&self, max_count: Option<usize>) -> Result<Page<usize, usize>> {
+            self.asked.lock().unwrap().push(max_count);
+            let end = self
+                .len
+                .min(self.start + max_count.unwrap_or(Self::MAX_PAGE_SIZE));
+            let next = (end < self.len).then_some(end);
+            Ok(((self.start..end).collect(), next))
+        }
+
+        fn seek(&mut self, next: usize) {
+            self.start = next;
+        }
+    }
+
+    fn numbers(len: usize, limit: Option<usize>) -> (Numbers, Arc<Mutex<Vec<Option<usize>>>>) {
+        let asked = Arc::default();
+        let listing = Numbers {
+            start: 0,
+            len,
+            limit,
+            asked: Arc::clone(&asked),
+        };
+        (listing, asked)
+    }
+
+    #[tokio::test]
+    async fn stream_fetches_every_page() {
+        let (listing, asked) = numbers(25, None);
+        let items: Vec<_> = into_stream(listing).try_collect().await.unwrap();
+        assert_eq!(items, (0..25).collect::<Vec<_>>());
+        assert_eq!(*asked.lock().unwrap(), [None, None, None]);
+    }
+
+    #[tokio::test]
+    async fn stream_stops_at_limit() {
+        let (listing, asked) = numbers(25, Some(12));
+        let items: Vec<_> = into_stream(listing).try_collect().await.unwrap();
+        assert_eq!(items, (0..12).collect::<Vec<_>>());
+        assert_eq!(*asked.lock().unwrap(), [Some(10), Some(2)]);
+    }
+
+    #[test]
+    fn page_size_without_limit() {
+        assert_eq!(page_size(None, None, 1000), None);
+        assert_eq!(page_size(Some(50), None, 1000), Some(50));
+    }
+
+    #[test]
+    fn page_size_stays_within_limit() {
+        assert_eq!(page_size(None, Some(10), 1000), Some(10));
+        assert_eq!(page_size(None, Some(5000), 1000), Some(1000));
+        assert_eq!(page_size(Some(50), Some(10), 1000), Some(10));
+        assert_eq!(page_size(Some(50), Some(5000), 1000), Some(50));
+    }
+}
@@ -0,0 +1,224 @@
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::{Error, Result};
+
+/// Decides whether and when failed B2 calls are sent again.
+///
+/// Only errors for which [`Error::is_retryable`] holds are retried, with an
+/// exponentially growing delay between attempts.
+/// The defaults follow the B2 integration guidelines: up to 5 attempts,
+/// starting at 1 second and doubling up to 64 seconds.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    respect_retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(64),
+            jitter: true,
+            respect_retry_after: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that sends every request only once.
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Total number of attempts, including the first one.
+    pub fn max_attempts(&mut self, max_attempts: u32) -> &mut Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry, doubled for every following one.
+    pub fn base_delay(&mut self, base_delay: Duration) -> &mut Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    pub fn max_delay(&mut self, max_delay: Duration) -> &mut Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Randomizes each delay to between half and all of its value, so that
+    /// clients failing together do not retry together.
+    pub fn jitter(&mut self, jitter: bool) -> &mut Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Waits as long as the `Retry-After` header asks for, when present,
+    /// instead of the computed delay, up to `max_delay`.
+    pub fn respect_retry_after(&mut self, respect_retry_after: bool) -> &mut Self {
+        self.respect_retry_after = respect_retry_after;
+        self
+    }
+
+    /// Calls `f` until it succeeds, fails with an error that is not
+    /// retryable, or the attempts run out.
+    pub(crate) async fn retry<F, Fut, T>(&self, f: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.retry_when(f, Error::is_retryable).await
+    }
+
+    /// Like [`RetryPolicy::retry`], with `retryable` deciding which errors
+    /// are worth another attempt.
+    pub(crate) async fn retry_when<F, Fut, T, P>(&self, mut f: F, retryable: P) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+        P: Fn(&Error) -> bool,
+    {
+        let mut attempt = 1;
+        loop {
+            match f().await {
+                Err(err) if retryable(&err) && attempt < self.max_attempts => {
+                    let delay = self.delay_after(attempt, &err);
+                    tracing::debug!(
+                        attempt,
+                        "retrying in {:?} after error: {}",
+                        delay,
+                        err.message()
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Delay before the next attempt after `attempt` failed with `err`,
+    /// never longer than `max_delay` even if B2 asks for more.
+    fn delay_after(&self, attempt: u32, err: &Error) -> Duration {
+        match err.retry_after() {
+            Some(retry_after) if self.respect_retry_after => retry_after.min(self.max_delay),
+            _ => self.delay(attempt),
+        }
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+
+        if self.jitter {
+            delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+        } else {
+            delay
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn policy() -> RetryPolicy {
+        let mut policy = RetryPolicy::default();
+        policy.jitter(false);
+        policy
+    }
+
+    #[test]
+    fn delay_doubles_up_to_max_delay() {
+        let policy = policy();
+        let delays: Vec<_> = (1..=9).map(|attempt| policy.delay(attempt)).collect();
+        let secs: Vec<_> = delays.iter().map(Duration::as_secs).collect();
+        assert_eq!(secs, [1, 2, 4, 8, 16, 32, 64, 64, 64]);
+
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(64));
+    }
+
+    #[test]
+    fn delay_with_jitter_stays_within_half_and_full() {
+        let policy = RetryPolicy::default();
+        for _ in 0..100 {
+            let delay = policy.delay(3);
+            assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(4));
+        }
+    }
+
+    #[test]
+    fn retry_after_is_capped_at_max_delay() {
+        let mut policy = policy();
+        policy.max_delay(Duration::from_secs(10));
+
+        let err = Error::new(ErrorKind::ServiceUnavailable, "busy")
+            .with_retry_after(Some(Duration::from_secs(5)));
+        assert_eq!(policy.delay_after(1, &err), Duration::from_secs(5));
+
+        let err = Error::new(ErrorKind::ServiceUnavailable, "busy")
+            .with_retry_after(Some(Duration::from_secs(86400)));
+        assert_eq!(policy.delay_after(1, &err), Duration::from_secs(10));
+
+        policy.respect_retry_after(false);
+        assert_eq!(policy.delay_after(1, &err), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn retryable_errors() {
+        let retryable = |kind| Error::new(kind, "").is_retryable();
+
+        assert!(retryable(ErrorKind::TooManyRequests));
+        assert!(retryable(ErrorKind::ServiceUnavailable));
+        assert!(retryable(ErrorKind::InternalError));
+        assert!(retryable(ErrorKind::RequestTimeout));
+        assert!(retryable(ErrorKind::Connect));
+        assert!(retryable(ErrorKind::Timeout));
+        assert!(retryable(ErrorKind::Request));
+
+        assert!(!retryable(ErrorKind::BadRequest));
+        assert!(!retryable(ErrorKind::Unauthorized));
+        assert!(!retryable(ErrorKind::ExpiredAuthToken));
+        assert!(!retryable(ErrorKind::NotFound));
+        assert!(!retryable(ErrorKind::Deserialize));
+    }
+
+    #[test]
+    fn retryable_uploads_include_rejected_tokens() {
+        let retryable = |kind| Error::new(kind, "").is_retryable_upload();
+
+        assert!(retryable(ErrorKind::ServiceUnavailable));
+        assert!(retryable(ErrorKind::ExpiredAuthToken));
+        assert!(retryable(ErrorKind::BadAuthToken));
+        assert!(!retryable(ErrorKind::BadRequest));
+    }
+
+    #[test]
+    fn non_idempotent_calls_are_not_retried_after_transport_errors() {
+        let retryable = |kind| Error::new(kind, "").is_retryable_non_idempotent();
+
+        assert!(retryable(ErrorKind::ServiceUnavailable));
+        assert!(retryable(ErrorKind::TooManyRequests));
+        assert!(retryable(ErrorKind::Connect));
+        assert!(!retryable(ErrorKind::Timeout));
+        assert!(!retryable(ErrorKind::Request));
+        assert!(!retryable(ErrorKind::BadRequest));
+    }
+}